@@ -2,7 +2,7 @@ use quicli::prelude::*;
 use structopt::StructOpt;
 
 use sql_composer::types::{SerdeValue, SqlComposition};
-use std::collections::{BTreeMap, HashMap};
+use std::collections::BTreeMap;
 
 use sql_composer::composer::ComposerConnection;
 
@@ -14,22 +14,12 @@ use serde_value::Value;
 use std::io;
 use std::path::Path;
 
-#[cfg(feature = "dbd-mysql")]
-use mysql::prelude::ToValue as MySqlToSql;
 #[cfg(feature = "dbd-mysql")]
 use mysql::Pool;
-#[cfg(feature = "dbd-mysql")]
-use mysql::Value as MySqlValue;
 
-#[cfg(feature = "dbd-postgres")]
-use postgres::types as pg_types;
-#[cfg(feature = "dbd-postgres")]
-use postgres::types::ToSql as PgToSql;
 #[cfg(feature = "dbd-postgres")]
 use postgres::{Connection as PgConnection, TlsMode as PgTlsMode};
 
-#[cfg(feature = "dbd-rusqlite")]
-pub use rusqlite::types::{Null, ToSql as RusqliteToSql, ValueRef as RusqliteValueRef};
 #[cfg(feature = "dbd-rusqlite")]
 use rusqlite::Connection as RusqliteConnection;
 
@@ -150,82 +140,15 @@ fn query_mysql(
 ) -> CliResult {
     let pool = Pool::new(uri).unwrap();
 
-    let values: BTreeMap<String, Vec<&dyn MySqlToSql>> =
-        params.iter().fold(BTreeMap::new(), |mut acc, (k, v)| {
-            let entry = acc.entry(k.to_string()).or_insert(vec![]);
-            *entry = v.iter().map(|x| x as &dyn MySqlToSql).collect();
-
-            acc
+    let request = params
+        .iter()
+        .fold(pool.composition(&comp), |request, (name, values)| {
+            request.bind_all(name, values)
         });
 
-    let (mut prep_stmt, bindings) = pool.compose(&comp, values, vec![], HashMap::new()).unwrap();
-
-    let driver_rows = prep_stmt.execute(bindings.as_slice()).unwrap();
-
-    let vv = driver_rows
-        .into_iter()
-        .fold(vec![], |mut value_maps, driver_row| {
-            let driver_row = driver_row.unwrap();
-
-            let bt: BTreeMap<Value, Value> = driver_row.columns_ref().iter().enumerate().fold(
-                BTreeMap::new(),
-                |mut acc, (i, column)| {
-                    let v = match driver_row.as_ref(i) {
-                        Some(MySqlValue::NULL) => Value::Unit,
-                        Some(MySqlValue::Bytes(b)) => {
-                            Value::String(String::from_utf8(b.to_vec()).unwrap())
-                        }
-                        Some(MySqlValue::Int(i)) => Value::I64(*i),
-                        Some(MySqlValue::UInt(u)) => Value::U64(*u),
-                        Some(MySqlValue::Float(f)) => Value::F64(*f),
-                        Some(MySqlValue::Date(
-                            year,
-                            month,
-                            day,
-                            hour,
-                            minutes,
-                            seconds,
-                            micro_seconds,
-                        )) => Value::Seq(vec![
-                            Value::U16(*year),
-                            Value::U8(*month),
-                            Value::U8(*day),
-                            Value::U8(*hour),
-                            Value::U8(*minutes),
-                            Value::U8(*seconds),
-                            Value::U32(*micro_seconds),
-                        ]),
-                        Some(MySqlValue::Time(
-                            is_negative,
-                            days,
-                            hours,
-                            minutes,
-                            seconds,
-                            micro_seconds,
-                        )) => Value::Seq(vec![
-                            Value::Bool(*is_negative),
-                            Value::U32(*days),
-                            Value::U8(*hours),
-                            Value::U8(*minutes),
-                            Value::U8(*seconds),
-                            Value::U32(*micro_seconds),
-                        ]),
-                        None => unreachable!("A none value isn't right"),
-                    };
-
-                    let _ = acc
-                        .entry(Value::String(column.name_str().to_string()))
-                        .or_insert(v);
-
-                    acc
-                },
-            );
-
-            value_maps.push(Value::Map(bt));
-            value_maps
-        });
+    let rows: Vec<Value> = request.query().unwrap();
 
-    output(Value::Seq(vv));
+    output(Value::Seq(rows));
 
     Ok(())
 }
@@ -238,52 +161,15 @@ fn query_postgres(
 ) -> CliResult {
     let conn = PgConnection::connect(uri, PgTlsMode::None).unwrap();
 
-    let values: BTreeMap<String, Vec<&dyn PgToSql>> =
-        params.iter().fold(BTreeMap::new(), |mut acc, (k, v)| {
-            let entry = acc.entry(k.to_string()).or_insert(vec![]);
-            *entry = v.iter().map(|x| x as &dyn PgToSql).collect();
-
-            acc
-        });
-
-    let (prep_stmt, bindings) = conn.compose(&comp, values, vec![], HashMap::new()).unwrap();
-
-    let driver_rows = &prep_stmt.query(&bindings).unwrap();
-
-    let vv = driver_rows
+    let request = params
         .iter()
-        .fold(vec![], |mut value_maps, driver_row| {
-            let bt: BTreeMap<Value, Value> = driver_row.columns().iter().enumerate().fold(
-                BTreeMap::new(),
-                |mut acc, (i, column)| {
-                    let v = match *column.type_() {
-                        pg_types::BOOL => Value::Bool(driver_row.get_opt(i).unwrap().unwrap()),
-                        pg_types::CHAR => Value::I8(driver_row.get_opt(i).unwrap().unwrap()),
-                        pg_types::INT2 => Value::I16(driver_row.get_opt(i).unwrap().unwrap()),
-                        pg_types::INT4 => Value::I32(driver_row.get_opt(i).unwrap().unwrap()),
-                        pg_types::OID => Value::U32(driver_row.get_opt(i).unwrap().unwrap()),
-                        pg_types::INT8 => Value::I64(driver_row.get_opt(i).unwrap().unwrap()),
-                        pg_types::VARCHAR | pg_types::TEXT | pg_types::NAME => {
-                            Value::String(driver_row.get_opt(i).unwrap().unwrap())
-                        }
-                        pg_types::FLOAT4 => Value::F32(driver_row.get_opt(i).unwrap().unwrap()),
-                        pg_types::FLOAT8 => Value::F64(driver_row.get_opt(i).unwrap().unwrap()),
-                        _ => unreachable!("shouldn't get here!"),
-                    };
-
-                    let _ = acc
-                        .entry(Value::String(column.name().to_string()))
-                        .or_insert(v);
-
-                    acc
-                },
-            );
-
-            value_maps.push(Value::Map(bt));
-            value_maps
+        .fold(conn.composition(&comp), |request, (name, values)| {
+            request.bind_all(name, values)
         });
 
-    output(Value::Seq(vv));
+    let rows: Vec<Value> = request.query().unwrap();
+
+    output(Value::Seq(rows));
 
     Ok(())
 }
@@ -309,56 +195,15 @@ fn query_rusqlite(
         }
     };
 
-    let values: BTreeMap<String, Vec<&dyn RusqliteToSql>> =
-        params.iter().fold(BTreeMap::new(), |mut acc, (k, v)| {
-            let entry = acc.entry(k.to_string()).or_insert(vec![]);
-            *entry = v.iter().map(|x| x as &dyn RusqliteToSql).collect();
-
-            acc
+    let request = params
+        .iter()
+        .fold(conn.composition(&comp), |request, (name, values)| {
+            request.bind_all(name, values)
         });
 
-    let (mut prep_stmt, bindings) = conn.compose(&comp, values, vec![], HashMap::new()).unwrap();
-
-    let column_names: Vec<String> = prep_stmt
-        .column_names()
-        .into_iter()
-        .map(String::from)
-        .collect();
-
-    let driver_rows = prep_stmt
-        .query_map(&bindings, |driver_row| {
-            let map = column_names.iter().enumerate().fold(
-                BTreeMap::new(),
-                |mut acc, (i, column_name)| {
-                    let _ = acc.entry(Value::String(column_name.to_string())).or_insert(
-                        match driver_row.get_raw(i) {
-                            RusqliteValueRef::Null => Value::Unit,
-                            RusqliteValueRef::Integer(int) => Value::I64(int),
-                            RusqliteValueRef::Real(r) => Value::F64(r),
-                            RusqliteValueRef::Text(t) => Value::String(t.to_string()),
-                            RusqliteValueRef::Blob(vc) => {
-                                let s = std::string::String::from_utf8(vc.to_vec()).unwrap();
-
-                                Value::String(s)
-                            }
-                        },
-                    );
-
-                    acc
-                },
-            );
-
-            Ok(map)
-        })
-        .unwrap();
-
-    let mut seq = vec![];
-
-    for driver_row in driver_rows {
-        seq.push(Value::Map(driver_row.unwrap()));
-    }
+    let rows: Vec<Value> = request.query().unwrap();
 
-    output(Value::Seq(seq));
+    output(Value::Seq(rows));
 
     Ok(())
 }
@@ -0,0 +1,191 @@
+//! `#[derive(BindValues)]` for `sql-composer`, binding each field of a struct by name.
+//!
+//! * a field is bound to its name as a single value
+//! * a `Vec<T>` field binds each of its values, as used by `IN (:bind(name))`, except `Vec<u8>`
+//!   which is bound as a blob
+//! * an `Option<T>` field binds NULL when it is `None`
+//! * `#[bind(rename = "name")]` binds a field to another name
+//! * `#[bind(skip)]` leaves a field unbound
+//! * `#[bind(flatten)]` binds the fields of a nested `BindValues` struct as if they were fields of
+//!   this one
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Field, Fields, GenericArgument, Lit, Meta,
+          NestedMeta, PathArguments, Result, Type};
+
+#[proc_macro_derive(BindValues, attributes(bind))]
+pub fn derive_bind_values(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(s) => match &s.fields {
+            Fields::Named(named) => &named.named,
+            _ => {
+                return Err(Error::new(
+                    Span::call_site(),
+                    "BindValues can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                Span::call_site(),
+                "BindValues can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut inserts = vec![];
+
+    for field in fields {
+        let attrs = FieldAttrs::from_field(field)?;
+
+        if attrs.skip {
+            continue;
+        }
+
+        inserts.push(bind_field(field, &attrs));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::sql_composer::types::value::BindValues for #name #ty_generics #where_clause {
+            fn bind_values(&self) -> ::std::result::Result<::std::collections::BTreeMap<::std::string::String, ::std::vec::Vec<::sql_composer::types::value::Value>>, ()> {
+                let mut values = ::std::collections::BTreeMap::new();
+
+                #(#inserts)*
+
+                Ok(values)
+            }
+        }
+    })
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    rename:  Option<String>,
+    skip:    bool,
+    flatten: bool,
+}
+
+impl FieldAttrs {
+    fn from_field(field: &Field) -> Result<Self> {
+        let mut attrs = Self::default();
+
+        for attr in field.attrs.iter().filter(|a| a.path.is_ident("bind")) {
+            let list = match attr.parse_meta()? {
+                Meta::List(list) => list,
+                meta => return Err(Error::new_spanned(meta, "expected #[bind(...)]")),
+            };
+
+            for nested in list.nested {
+                match nested {
+                    NestedMeta::Meta(Meta::Path(p)) if p.is_ident("skip") => attrs.skip = true,
+                    NestedMeta::Meta(Meta::Path(p)) if p.is_ident("flatten") => {
+                        attrs.flatten = true
+                    }
+                    NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("rename") => {
+                        match nv.lit {
+                            Lit::Str(s) => attrs.rename = Some(s.value()),
+                            lit => return Err(Error::new_spanned(lit, "expected a string")),
+                        }
+                    }
+                    nested => {
+                        return Err(Error::new_spanned(
+                            nested,
+                            "expected rename = \"name\", skip or flatten",
+                        ))
+                    }
+                }
+            }
+        }
+
+        Ok(attrs)
+    }
+}
+
+fn bind_field(field: &Field, attrs: &FieldAttrs) -> TokenStream2 {
+    let ident = field.ident.as_ref().expect("named field");
+
+    if attrs.flatten {
+        return quote! {
+            values.extend(::sql_composer::types::value::BindValues::bind_values(&self.#ident)?);
+        };
+    }
+
+    let name = match &attrs.rename {
+        Some(rename) => rename.to_string(),
+        None => ident.to_string(),
+    };
+
+    let multiple = match wrapped_type(&field.ty, "Vec") {
+        Some(t) => !is_type(t, "u8"),
+        None => false,
+    };
+
+    let bound = if multiple {
+        quote! {
+            self.#ident
+                .iter()
+                .map(::sql_composer::types::value::ToValue::to_value)
+                .collect::<::std::result::Result<::std::vec::Vec<_>, ()>>()?
+        }
+    }
+    else if wrapped_type(&field.ty, "Option").is_some() {
+        quote! {
+            vec![match &self.#ident {
+                Some(v) => ::sql_composer::types::value::ToValue::to_value(v)?,
+                None => ::sql_composer::types::value::Value::Null,
+            }]
+        }
+    }
+    else {
+        quote! {
+            vec![::sql_composer::types::value::ToValue::to_value(&self.#ident)?]
+        }
+    };
+
+    quote! {
+        values.insert(#name.to_string(), #bound);
+    }
+}
+
+/// The type parameter of `ty` when it is the generic type `wrapper`, such as `T` of `Vec<T>`.
+fn wrapped_type<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let segment = match ty {
+        Type::Path(p) if p.qself.is_none() => p.path.segments.last()?,
+        _ => return None,
+    };
+
+    if segment.ident != wrapper {
+        return None;
+    }
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            GenericArgument::Type(t) => Some(t),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_type(ty: &Type, name: &str) -> bool {
+    match ty {
+        Type::Path(p) => p.qself.is_none() && p.path.is_ident(name),
+        _ => false,
+    }
+}
@@ -0,0 +1,88 @@
+use sql_composer::composer::shared::SharedComposer;
+use sql_composer::parser::parse_template;
+use sql_composer::types::value::{BindValues, Value};
+use sql_composer::types::Span;
+
+use std::collections::HashMap;
+
+#[derive(BindValues)]
+struct Person {
+    id:       i64,
+    #[bind(rename = "full_name")]
+    name:     String,
+    nickname: Option<String>,
+    data:     Vec<u8>,
+    #[bind(skip)]
+    #[allow(dead_code)]
+    password: String,
+}
+
+#[derive(BindValues)]
+struct Search<'a> {
+    ids:    Vec<i64>,
+    name:   &'a str,
+    #[bind(flatten)]
+    page:   Page,
+}
+
+#[derive(BindValues)]
+struct Page {
+    limit:  i64,
+    offset: i64,
+}
+
+#[test]
+fn test_bind_fields() {
+    let person = Person {
+        id:       1,
+        name:     "Steven".to_string(),
+        nickname: None,
+        data:     vec![1, 2],
+        password: "secret".to_string(),
+    };
+
+    let values = person.bind_values().expect("bind_values should work");
+
+    let names: Vec<&str> = values.keys().map(|k| k.as_str()).collect();
+
+    assert_eq!(names, vec!["data", "full_name", "id", "nickname"], "renamed and skipped");
+
+    assert_eq!(values["id"], vec![Value::Integer(1)]);
+    assert_eq!(values["full_name"], vec![Value::Text("Steven".into())]);
+    assert_eq!(values["nickname"], vec![Value::Null], "None binds NULL");
+    assert_eq!(values["data"], vec![Value::Blob(vec![1, 2])], "Vec<u8> binds a blob");
+}
+
+#[test]
+fn test_bind_multiple_values() {
+    let search = Search {
+        ids:  vec![1, 2, 3],
+        name: "Kenny",
+        page: Page {
+            limit:  10,
+            offset: 20,
+        },
+    };
+
+    let values = search.bind_values().expect("bind_values should work");
+
+    assert_eq!(
+        values["ids"],
+        vec![Value::Integer(1), Value::Integer(2), Value::Integer(3)],
+        "each value of a Vec"
+    );
+    assert_eq!(values["limit"], vec![Value::Integer(10)], "flattened");
+    assert_eq!(values["offset"], vec![Value::Integer(20)], "flattened");
+
+    let (_remaining, stmt) = parse_template(Span::new("SELECT id FROM person AS main WHERE id IN (:bind(ids EXPECTING MIN 1)) AND name <> :bind(name) :paginate(limit, offset);"), None).unwrap();
+
+    let (bound_sql, bindings) = SharedComposer::postgres()
+        .compose(&stmt.item, &values, &HashMap::new())
+        .expect("compose should work");
+
+    assert_eq!(
+        bound_sql,
+        "SELECT id FROM person AS main WHERE id IN ( $1, $2, $3 ) AND name <> $4 LIMIT $5 OFFSET $6;"
+    );
+    assert_eq!(bindings.len(), 6, "bound values");
+}
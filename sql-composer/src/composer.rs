@@ -1,4 +1,6 @@
+pub mod dialect;
 pub mod direct;
+pub mod shared;
 
 #[cfg(feature = "dbd-mysql")]
 pub mod mysql;
@@ -11,24 +13,193 @@ pub mod rusqlite;
 pub use crate::parser::bind_value_named_set;
 pub use crate::parser::parse_template;
 
-use crate::types::{ParsedItem, Sql, SqlBinding, SqlComposition, SqlCompositionAlias, SqlDbObject};
+use crate::types::value::{LiteralStyle, Value};
+use crate::types::{ParsedItem, Sql, SqlBinding, SqlComposition, SqlCompositionAlias, SqlDbObject,
+                   SqlIdentifier, SqlOrderBy, SqlPagination, SqlPredicate};
+use std::cell::{Ref, RefCell};
 use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+#[cfg(feature = "composer-serde")]
+use serde::de::DeserializeOwned;
+
+#[cfg(feature = "composer-serde")]
+use serde_value::Value as SerdeValueEnum;
 
 pub trait ComposerConnection<'a> {
-    type Composer;
-    //TODO: this should be Composer::Value but can't be specified as Self::Value::Connection
+    type Composer: Composer<Value = Self::Value>;
     type Value;
     type Statement;
 
-    fn compose(
-        &'a self,
-        s: &SqlComposition,
-        values: BTreeMap<String, Vec<Self::Value>>,
-        root_mock_values: Vec<BTreeMap<String, Self::Value>>,
-        mock_values: HashMap<SqlCompositionAlias, Vec<BTreeMap<String, Self::Value>>>,
-    ) -> Result<(Self::Statement, Vec<Self::Value>), ()>;
+    /// A composer for the dialect of this connection.
+    fn composer(&self) -> Self::Composer;
+
+    /// Prepares SQL composed by `composer()` as a statement of this connection.
+    fn prepare_sql(&'a self, sql: &str) -> Result<Self::Statement, ()>;
+
+    /// Executes a statement prepared by `prepare_sql()`, mapping each row it returns to a map of
+    /// its column names and values.
+    #[cfg(feature = "composer-serde")]
+    fn query_values(
+        &self,
+        stmt: &mut Self::Statement,
+        values: &[Self::Value],
+    ) -> Result<Vec<SerdeValueEnum>, ()>;
+
+    /// Starts a request to compose and prepare `s` on this connection, with values and mocks added
+    /// by the returned `ComposeRequest`.
+    fn composition<'c>(&'a self, s: &'c SqlComposition) -> ComposeRequest<'a, 'c, Self>
+    where
+        Self: Sized,
+    {
+        ComposeRequest::new(self, s)
+    }
+}
+
+/// Borrows a value as the value type bound by a connection, so a `ComposeRequest` accepts anything
+/// its driver can bind.
+pub trait BindValue<'a, V> {
+    fn bind_value(&'a self) -> V;
+}
+
+/// The values and mocks to compose a statement with, before it's prepared on a connection.
+///
+/// ```ignore
+/// let (stmt, bindings) = conn
+///     .composition(&comp)
+///     .bind("name", &name)
+///     .bind_all("ids", &ids)
+///     .mock_table("users", rows)
+///     .prepare()?;
+/// ```
+pub struct ComposeRequest<'a, 'c, C: ComposerConnection<'a>> {
+    connection:  &'a C,
+    composition: &'c SqlComposition,
+    values:      Binds<C::Value>,
+    mocks:       Mocks<C::Value>,
+}
+
+impl<'a, 'c, C: ComposerConnection<'a>> ComposeRequest<'a, 'c, C> {
+    pub fn new(connection: &'a C, composition: &'c SqlComposition) -> Self {
+        Self {
+            connection,
+            composition,
+            values: BTreeMap::new(),
+            mocks: HashMap::new(),
+        }
+    }
+
+    /// Binds a single value to `name`, replacing any values already bound to it.
+    pub fn bind<T: BindValue<'a, C::Value>>(mut self, name: &str, value: &'a T) -> Self {
+        self.values.insert(name.to_string(), vec![value.bind_value()]);
+
+        self
+    }
+
+    /// Binds each of `values` to `name`, replacing any values already bound to it.
+    pub fn bind_all<I, T>(mut self, name: &str, values: I) -> Self
+    where
+        I: IntoIterator<Item = &'a T>,
+        T: BindValue<'a, C::Value> + 'a,
+    {
+        self.values.insert(
+            name.to_string(),
+            values.into_iter().map(|v| v.bind_value()).collect(),
+        );
+
+        self
+    }
+
+    /// Binds each name of `values`, such as the map built by `#[derive(BindValues)]` or
+    /// `to_bind_values()`, replacing any values already bound to those names.
+    pub fn bind_map<T>(mut self, values: &'a Binds<T>) -> Self
+    where
+        T: BindValue<'a, C::Value> + 'a,
+    {
+        for (name, v) in values {
+            self.values
+                .insert(name.to_string(), v.iter().map(|v| v.bind_value()).collect());
+        }
+
+        self
+    }
+
+    /// Composes `rows` in place of each use of `table`.
+    pub fn mock_table<I, R, K>(self, table: &str, rows: I) -> Self
+    where
+        I: IntoIterator<Item = R>,
+        R: IntoIterator<Item = (K, C::Value)>,
+        K: Into<String>,
+    {
+        let alias = SqlCompositionAlias::DbObject(SqlDbObject {
+            object_name:  table.to_string(),
+            object_alias: None,
+        });
+
+        self.mock(alias, rows)
+    }
+
+    /// Composes `rows` in place of each `:compose()` of the template at `path`.
+    pub fn mock_path<P, I, R, K>(self, path: P, rows: I) -> Self
+    where
+        P: Into<PathBuf>,
+        I: IntoIterator<Item = R>,
+        R: IntoIterator<Item = (K, C::Value)>,
+        K: Into<String>,
+    {
+        self.mock(SqlCompositionAlias::Path(path.into()), rows)
+    }
+
+    fn mock<I, R, K>(mut self, alias: SqlCompositionAlias, rows: I) -> Self
+    where
+        I: IntoIterator<Item = R>,
+        R: IntoIterator<Item = (K, C::Value)>,
+        K: Into<String>,
+    {
+        let rows = rows
+            .into_iter()
+            .map(|row| row.into_iter().map(|(k, v)| (k.into(), v)).collect())
+            .collect();
+
+        self.mocks.insert(alias, rows);
+
+        self
+    }
+
+    /// Composes the SQL and values of the request, without preparing it.
+    pub fn compose(&self) -> Result<(String, Vec<C::Value>), ()> {
+        self.connection
+            .composer()
+            .compose(self.composition, &self.values, &self.mocks)
+    }
+
+    /// Composes the request and prepares it on the connection.
+    pub fn prepare(self) -> Result<(C::Statement, Vec<C::Value>), ()> {
+        let (sql, bindings) = self.compose()?;
+
+        let stmt = self.connection.prepare_sql(&sql)?;
+
+        Ok((stmt, bindings))
+    }
+
+    /// Composes and prepares the request, then deserializes each row it returns into a `T`.
+    #[cfg(feature = "composer-serde")]
+    pub fn query<T: DeserializeOwned>(self) -> Result<Vec<T>, ()> {
+        let connection = self.connection;
+
+        let (mut stmt, bindings) = self.prepare()?;
+
+        let rows = connection.query_values(&mut stmt, &bindings)?;
+
+        //TODO: useful error
+        rows.into_iter()
+            .map(|row| row.deserialize_into().map_err(|_| ()))
+            .collect()
+    }
 }
 
+/// Builds the rows of a mock in tests; at runtime use `ComposeRequest::mock_table()` or
+/// `ComposeRequest::mock_path()`.
 #[macro_export]
 macro_rules! mock_values(
     ($to_type:ty: $({$($key:literal => $value:expr), +}), +) => {
@@ -107,6 +278,8 @@ macro_rules! mock_db_object_values(
      };
 );
 
+/// Builds a map of bind values in tests; at runtime use `ComposeRequest::bind()` or
+/// `ComposeRequest::bind_all()`.
 #[macro_export]
 macro_rules! bind_values(
     ($to_type:ty: $( $key:literal => [$($value:expr), +]), +)  => {
@@ -128,32 +301,457 @@ macro_rules! bind_values(
      };
 );
 
+/// Handles a project specific `:command([distinct] [column1, column2 of] t1.tql, ...)` macro.
+///
+/// `expand` receives the parsed composition, with its columns, distinct/all flags, `of` list and
+/// already loaded aliases, and returns the composition to compose in its place. Bindings pushed
+/// onto the returned composition are numbered and collected by the composer exactly like a
+/// `:bind()` written in a template.
+///
+/// A handler binds values of its own, such as a cutoff computed when it is called, by returning
+/// them by name along with the composition. As the composer's values are borrowed from the caller,
+/// those are collected by `Composer::expand_macros()` before composing, and bound by the caller
+/// along with its own values so they get placeholders like any other value. Composing a handler
+/// that returns values without expanding it first is an error.
+///
+/// Handlers are registered on a composer with `insert_macro()` and are only consulted for
+/// commands the composer doesn't handle itself. They are shared by every call to compose, so they
+/// must be safe to use from several threads at once.
+pub trait MacroHandler: Send + Sync {
+    fn expand(&self, composition: &SqlComposition) -> Result<(SqlComposition, Binds<Value>), ()>;
+}
+
+/// How a dialect limits the rows returned by `:paginate()` and `:paginate_after()`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PaginationStyle {
+    /// `LIMIT ? OFFSET ?`, as used by SQLite, MySQL and PostgreSQL
+    #[default]
+    LimitOffset,
+    /// `OFFSET ? ROWS FETCH NEXT ? ROWS ONLY`, as used by SQL Server and Oracle
+    OffsetFetch,
+}
+
+/// How a dialect quotes identifiers substituted by `:ident()`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum IdentifierQuoting {
+    /// `"name"`, as used by SQLite and PostgreSQL
+    #[default]
+    DoubleQuote,
+    /// `` `name` ``, as used by MySQL
+    Backtick,
+    /// `[name]`, as used by SQL Server
+    Bracket,
+}
+
+impl IdentifierQuoting {
+    /// Quotes each part of a possibly qualified identifier, doubling any embedded quote characters.
+    pub fn quote(&self, ident: &str) -> String {
+        let (open, close) = match self {
+            IdentifierQuoting::DoubleQuote => ('"', '"'),
+            IdentifierQuoting::Backtick => ('`', '`'),
+            IdentifierQuoting::Bracket => ('[', ']'),
+        };
+
+        ident
+            .split('.')
+            .map(|part| {
+                let escaped = part.replace(close, &format!("{}{}", close, close));
+
+                format!("{}{}{}", open, escaped, close)
+            })
+            .collect::<Vec<String>>()
+            .join(".")
+    }
+}
+
 #[derive(Default)]
 pub struct ComposerConfig {
     #[allow(dead_code)]
     start: usize,
 }
 
+/// The values bound by name for a single call to compose.
+pub type Binds<V> = BTreeMap<String, Vec<V>>;
+
+/// Rows of values composed in place of the paths and tables they're keyed by, for a single call
+/// to compose.
+pub type Mocks<V> = HashMap<SqlCompositionAlias, Vec<BTreeMap<String, V>>>;
+
+/// A composed statement, or part of one, and the values bound to its placeholders in order.
+pub type Composed<V> = (String, Vec<V>);
+
+/// The keyset predicate of a `:paginate_after()`, when its cursor is bound, the clause limiting the
+/// rows and the values for both.
+pub type ComposedPagination<V> = (Option<String>, String, Vec<V>);
+
+/// A page of a statement and the statement counting all of its rows, as from `compose_page()`.
+pub type ComposedPage<V> = (Composed<V>, Composed<V>);
+
+/// Borrows a map of driver independent values as the values a composer binds, such as
+/// `Binds<&dyn ToSql>` for rusqlite and postgres, so one map can be handed to any composer.
+pub fn value_binds<'a, V>(values: &'a Binds<Value>) -> Binds<V>
+where
+    Value: BindValue<'a, V>,
+{
+    values
+        .iter()
+        .map(|(name, v)| (name.to_string(), v.iter().map(|v| v.bind_value()).collect()))
+        .collect()
+}
+
+/// The placeholders given out while composing a single statement, for dialects that refer to a
+/// value more than once or name their placeholders.
+#[derive(Debug, Default)]
+pub struct BindingTags {
+    /// The position first given to each name bound to a single value
+    pub first: HashMap<String, usize>,
+    /// The name given to the placeholder at each position
+    pub named: BTreeMap<usize, String>,
+}
+
+impl BindingTags {
+    /// Names the placeholder at position `u` for the binding `name`: the name itself when it's
+    /// bound to a single value, otherwise suffixed with the position, and counted up from there
+    /// until it differs from every name given out or reserved so far.
+    pub fn named_tag(&mut self, u: usize, name: &str) -> String {
+        let single = self.first.get(name) == Some(&u);
+
+        let base = placeholder_name(name);
+
+        let mut tag = base.clone();
+
+        if !single || self.tag_taken(&tag, name) {
+            tag = format!("{}_{}", base, u);
+        }
+
+        let mut count = 0;
+
+        while self.tag_taken(&tag, name) {
+            count += 1;
+            tag = format!("{}_{}_{}", base, u, count);
+        }
+
+        self.named.insert(u, tag.clone());
+
+        tag
+    }
+
+    /// The placeholder name already given to the single value bound to `name`, recording `u` as
+    /// its position when it's the first use of the name.
+    pub fn reuse_named_tag(&mut self, u: usize, name: &str) -> Option<String> {
+        match self.first.get(name) {
+            Some(first) => self.named.get(first).cloned(),
+            None => {
+                self.first.insert(name.to_string(), u);
+
+                None
+            }
+        }
+    }
+
+    /// Pairs each composed value with the name of its placeholder, prefixed with a colon.
+    pub fn named_values<V>(&self, values: Vec<V>) -> Result<Vec<(String, V)>, ()> {
+        let mut named_values = vec![];
+
+        for (i, value) in values.into_iter().enumerate() {
+            match self.named.get(&(i + 1)) {
+                Some(tag) => named_values.push((format!(":{}", tag), value)),
+                //TODO: useful error
+                None => return Err(()),
+            }
+        }
+
+        Ok(named_values)
+    }
+
+    fn tag_taken(&self, tag: &str, name: &str) -> bool {
+        self.named.values().any(|t| t == tag)
+            || self
+                .first
+                .keys()
+                .any(|n| n != name && placeholder_name(n) == tag)
+    }
+}
+
+/// The name of a named placeholder for a binding, with the dots of a nested name such as
+/// `page.limit` written as `__`, as a placeholder can't hold a dot.
+pub fn placeholder_name(name: &str) -> String {
+    name.replace('.', "__")
+}
+
+/// The dialect of a database: how its placeholders, identifiers and pagination are written, and
+/// the macros it knows. Values are passed to each call to compose, so nothing is kept from one
+/// call to the next and a single composer can serve any number of requests at once.
 pub trait Composer: Sized {
-    type Value: Copy;
+    type Value: Clone;
+
+    fn compose(
+        &self,
+        s: &SqlComposition,
+        values: &Binds<Self::Value>,
+        mocks: &Mocks<Self::Value>,
+    ) -> Result<(String, Vec<Self::Value>), ()> {
+        ComposerContext::new(self, values, mocks).compose(s)
+    }
+
+    /// Composes a page of `s`, limited by the bindings named in `page`, along with a statement
+    /// counting every row of `s`. Each statement has its own values.
+    ///
+    /// The pagination is added to the end of `s` itself rather than to a query wrapping it, so it
+    /// follows the ORDER BY of `s`, as OFFSET/FETCH requires.
+    fn compose_page(
+        &self,
+        s: &SqlComposition,
+        page: &SqlPagination,
+        values: &Binds<Self::Value>,
+        mocks: &Mocks<Self::Value>,
+    ) -> Result<ComposedPage<Self::Value>, ()> {
+        let command = Some("PAGE".to_string());
+
+        let sub = ParsedItem::generated(s.clone(), command.clone()).unwrap();
+
+        let pagination = Sql::Pagination(ParsedItem::generated(page.clone(), command.clone()).unwrap());
+
+        let out = if s.command.is_none() {
+            let mut out = s.clone();
+
+            let end = out
+                .sql
+                .iter()
+                .rposition(|c| matches!(c, Sql::Ending(_)))
+                .unwrap_or(out.sql.len());
+
+            out.sql.insert(end, pagination);
+
+            out
+        }
+        else {
+            // a command is composed in place of its own sql, so it is followed by the pagination
+            let mut out = SqlComposition::default();
+
+            out.push_sub_comp(sub.clone()).unwrap();
+            out.push_sql(pagination).unwrap();
+            out.push_generated_end(command.clone()).unwrap();
+
+            out
+        };
+
+        let page_item = ParsedItem::generated(out, command.clone()).unwrap();
+
+        let alias = SqlCompositionAlias::DbObject(SqlDbObject::new("page_main".into(), None).unwrap());
+
+        let mut count = SqlComposition {
+            command: Some(ParsedItem::generated("count".into(), command.clone()).unwrap()),
+            of: vec![ParsedItem::generated(alias.clone(), command.clone()).unwrap()],
+            ..Default::default()
+        };
+
+        let counted = if s.command.is_none() {
+            ParsedItem::generated(without_order_by(s), command.clone()).unwrap()
+        }
+        else {
+            sub
+        };
+
+        count.aliases.insert(alias, counted);
+
+        let count_item = ParsedItem::generated(count, command).unwrap();
+
+        let page_statement =
+            ComposerContext::new(self, values, mocks).compose_statement(&page_item, 1usize, false)?;
+
+        let count_statement =
+            ComposerContext::new(self, values, mocks).compose_count_command(&count_item, 1usize, false)?;
+
+        Ok((page_statement, count_statement))
+    }
+
+    /// Composes `s` as few times as needed to keep each statement within the backend's bind
+    /// parameter limit, splitting up the rows bound to the `:values()` or `:bind_rows()` named
+    /// `rows`.
+    fn compose_chunked(
+        &self,
+        s: &SqlComposition,
+        rows: &str,
+        values: &Binds<Self::Value>,
+        mocks: &Mocks<Self::Value>,
+    ) -> Result<Vec<Composed<Self::Value>>, ()> {
+        let (sql, bound) = self.compose(s, values, mocks)?;
+
+        if bound.len() <= self.max_bind_params() {
+            return Ok(vec![(sql, bound)]);
+        }
+
+        let width = match s.find_binding(rows).and_then(|b| b.columns.as_ref()) {
+            Some(c) if !c.is_empty() => c.len(),
+            //TODO: useful error
+            _ => return Err(()),
+        };
+
+        let all_rows = match values.get(rows) {
+            Some(v) => v,
+            None => return Err(()),
+        };
+
+        let other_params = bound.len() - all_rows.len();
+
+        if other_params >= self.max_bind_params() {
+            //TODO: useful error
+            return Err(());
+        }
+
+        let chunk_rows = (self.max_bind_params() - other_params) / width;
+
+        if chunk_rows == 0 {
+            //TODO: useful error
+            return Err(());
+        }
+
+        let mut chunk_values = values.clone();
+
+        let mut statements = vec![];
+
+        for chunk in all_rows.chunks(chunk_rows * width) {
+            chunk_values.insert(rows.to_string(), chunk.to_vec());
+
+            statements.push(self.compose(s, &chunk_values, mocks)?);
+        }
+
+        Ok(statements)
+    }
+
+    /// The most bind parameters the backend accepts in one statement.
+    fn max_bind_params(&self) -> usize {
+        65535
+    }
+
+    fn identifier_quoting(&self) -> IdentifierQuoting {
+        IdentifierQuoting::default()
+    }
+
+    fn pagination_style(&self) -> PaginationStyle {
+        PaginationStyle::default()
+    }
+
+    /// The LIMIT written ahead of the OFFSET of `:paginate()` when only the offset is bound, for
+    /// dialects not accepting an OFFSET without a LIMIT. None writes the OFFSET on its own.
+    fn unbounded_limit(&self) -> Option<&str> {
+        None
+    }
+
+    /// Writes the alias of a derived table, such as a mocked table or the statement counted by
+    /// `:count()`.
+    fn table_alias(&self, alias: &str) -> String {
+        format!("AS {}", alias)
+    }
+
+    /// The table each row of a mocked table is selected FROM, for dialects not accepting a SELECT
+    /// without one.
+    fn dummy_table(&self) -> Option<&str> {
+        None
+    }
+
+    /// How values are written into the sql by composers inlining them.
+    fn literal_style(&self) -> LiteralStyle {
+        LiteralStyle::default()
+    }
+
+    fn value_text(&self, value: &Self::Value) -> Result<String, ()>;
+
+    /// Composes a single bound value at position `u`, as a placeholder along with the value by
+    /// default. Composers writing values into the sql return the literal and no values instead.
+    fn compose_value(
+        &self,
+        u: usize,
+        name: String,
+        value: &Self::Value,
+        tags: &mut BindingTags,
+    ) -> Result<(String, Vec<Self::Value>), ()> {
+        Ok((self.binding_tag(u, name, tags), vec![value.clone()]))
+    }
+
+    /// Whether a binding written as an IN list is sent as the array bound to it instead, composed
+    /// as `= ANY(?)` or `<> ALL(?)` for NOT IN, given the values bound to the binding.
+    fn array_binding(&self, _binding: &SqlBinding, _values: &[Self::Value]) -> bool {
+        false
+    }
+
+    fn binding_tag(&self, u: usize, name: String, tags: &mut BindingTags) -> String;
+
+    /// The tag of a placeholder already holding the single value bound to `name` in the statement
+    /// being composed, for dialects able to refer to a parameter more than once. Otherwise None,
+    /// and the value is bound again at position `u`.
+    fn reuse_binding_tag(&self, _u: usize, _name: &str, _tags: &mut BindingTags) -> Option<String> {
+        None
+    }
+
+    fn get_macro(&self, name: String) -> Option<&dyn MacroHandler>;
+
+    fn insert_macro(&mut self, name: String, handler: Box<dyn MacroHandler>);
+
+    /// Replaces each command of `s` handled by a registered `MacroHandler` with the composition
+    /// it returns, returning the expanded composition and the values the handlers bound. Bind
+    /// those along with `values` to compose it. A handler binding a name `values` or another
+    /// handler already binds to different values is an error.
+    fn expand_macros(
+        &self,
+        s: &SqlComposition,
+        values: &Binds<Self::Value>,
+    ) -> Result<(SqlComposition, Binds<Value>), ()>
+    where
+        Self: Sized,
+    {
+        let mut macro_values = BTreeMap::new();
+
+        let expanded = expand_composition(self, s, values, &mut macro_values)?;
+
+        Ok((expanded, macro_values))
+    }
 
-    fn compose(&self, s: &SqlComposition) -> Result<(String, Vec<Self::Value>), ()> {
+    fn config() -> ComposerConfig;
+}
+
+/// A single call to compose, holding its values and the placeholders given out so far apart from
+/// the composer.
+pub struct ComposerContext<'a, C: Composer> {
+    composer: &'a C,
+    values:   &'a Binds<C::Value>,
+    mocks:    &'a Mocks<C::Value>,
+    tags:     RefCell<BindingTags>,
+}
+
+impl<'a, C: Composer> ComposerContext<'a, C> {
+    pub fn new(composer: &'a C, values: &'a Binds<C::Value>, mocks: &'a Mocks<C::Value>) -> Self {
+        Self {
+            composer,
+            values,
+            mocks,
+            tags: RefCell::new(BindingTags::default()),
+        }
+    }
+
+    pub fn compose(&self, s: &SqlComposition) -> Result<(String, Vec<C::Value>), ()> {
         let item = ParsedItem::generated(s.clone(), None).unwrap();
 
         self.compose_statement(&item, 1usize, false)
     }
 
-    fn compose_statement(
+    /// The placeholders given out by this call so far.
+    pub fn binding_tags(&self) -> Ref<'_, BindingTags> {
+        self.tags.borrow()
+    }
+
+    pub fn compose_statement(
         &self,
         sc: &ParsedItem<SqlComposition>,
         offset: usize,
         child: bool,
-    ) -> Result<(String, Vec<Self::Value>), ()> {
+    ) -> Result<(String, Vec<C::Value>), ()> {
         let mut i = offset;
 
         let mut sql = String::new();
 
-        let mut values: Vec<Self::Value> = vec![];
+        let mut values: Vec<C::Value> = vec![];
 
         if sc.item.command.is_some() {
             return self.compose_command(&sc, i, true);
@@ -163,14 +761,92 @@ pub trait Composer: Sized {
         let mut skip_this;
         let mut skip_next = false;
 
-        for c in &sc.item.sql {
+        // WHERE is held back until something follows it, so predicates can drop their conjunction
+        // and a WHERE left without conditions is removed
+        let mut where_keyword: Option<String> = None;
+        let mut where_predicates = false;
+        let mut where_seen = false;
+        let mut depth = 0;
+
+        // set after an array binding replaced an IN list, so its closing paren is dropped
+        let mut close_array = false;
+
+        // the chosen branch of each :if() is composed in line, so a WHERE held back ahead of it is
+        // still pending for the predicates inside of it
+        for c in self.expand_conditionals(&sc.item.sql) {
             pad = true;
             skip_this = skip_next;
             skip_next = false;
 
             let (sub_sql, sub_values) = match c {
-                Sql::Literal(t) => (t.to_string(), vec![]),
-                Sql::Binding(b) => self.compose_binding(b.item.clone(), i)?,
+                Sql::Literal(t) => {
+                    let literal = t.to_string();
+
+                    if scan_where_keyword(&literal, &mut depth) {
+                        where_seen = true;
+                    }
+
+                    let literal = if close_array && literal.starts_with(')') {
+                        literal[1..].trim_start().to_string()
+                    }
+                    else {
+                        literal
+                    };
+
+                    close_array = false;
+
+                    match split_where_keyword(&literal) {
+                        Some((sub_sql, keyword)) => {
+                            let sub_sql = sub_sql.to_string();
+
+                            where_keyword = Some(keyword.to_string());
+                            where_predicates = false;
+
+                            if sub_sql.is_empty() {
+                                continue;
+                            }
+
+                            if !sql.is_empty() {
+                                sql.push(' ');
+                            }
+
+                            sql.push_str(&sub_sql);
+
+                            continue;
+                        }
+                        None => (literal, vec![]),
+                    }
+                }
+                Sql::Keyword(k) if k.item.value.to_uppercase() == "WHERE" => {
+                    where_keyword = Some(k.to_string());
+                    where_predicates = false;
+
+                    if depth == 0 {
+                        where_seen = true;
+                    }
+
+                    continue;
+                }
+                Sql::Binding(b) => {
+                    let in_list = match self.get_values(b.item.name.to_string()) {
+                        Some(v) if self.composer.array_binding(&b.item, v) => split_in_list(&sql),
+                        _ => None,
+                    };
+
+                    match in_list {
+                        Some((head, negated)) => {
+                            sql.truncate(head);
+                            close_array = true;
+
+                            let (tag, array_values) = self.compose_array_binding(&b.item, i)?;
+
+                            let op = if negated { "<> ALL" } else { "= ANY" };
+
+                            (format!("{}({})", op, tag), array_values)
+                        }
+                        None => self.compose_binding(b.item.clone(), i)?,
+                    }
+                }
                 Sql::Composition((ss, _aliases)) => self.compose_statement(&ss, i, true)?,
                 Sql::Ending(e) => {
                     pad = false;
@@ -187,12 +863,16 @@ pub trait Composer: Sized {
                         SqlDbObject::new(dbo.item.object_name.to_string(), None).unwrap(),
                     );
 
-                    if let Some(mv) = self.mock_values().get(&dbo_alias) {
-                        let (mock_sql, mock_values) = self.mock_compose(mv, i);
+                    if let Some(mv) = self.mocks.get(&dbo_alias) {
+                        let (mock_sql, mock_values) = self.mock_compose(mv, i)?;
 
                         //TODO: this should call the alias function on dbo_alias, which uses
                         //object_alias but falls back to object_name
-                        let mock_sql = format!("( {} ) AS {}", mock_sql, dbo.item.object_name);
+                        let mock_sql = format!(
+                            "( {} ) {}",
+                            mock_sql,
+                            self.composer.table_alias(&dbo.item.object_name)
+                        );
 
                         (mock_sql, mock_values)
                     }
@@ -201,12 +881,107 @@ pub trait Composer: Sized {
                     }
                 }
                 Sql::Keyword(k) => (k.to_string(), vec![]),
+                // expanded by expand_conditionals()
+                Sql::Conditional(_) => continue,
+                Sql::Identifier(ident) => (self.compose_identifier(&ident.item)?, vec![]),
+                Sql::OrderBy(order) => (self.compose_order_by(&order.item)?, vec![]),
+                Sql::Pagination(p) => {
+                    let (predicate, clause, page_values) = self.compose_pagination(&p.item, i)?;
+
+                    let mut page_sql = match predicate {
+                        Some(predicate) => {
+                            let keyword = match where_keyword.take() {
+                                Some(w) => w,
+                                None if where_seen => {
+                                    group_where_conditions(&mut sql);
+
+                                    "AND".to_string()
+                                }
+                                None => "WHERE".to_string(),
+                            };
+
+                            format!("{} {}", keyword, predicate)
+                        }
+                        None => {
+                            where_predicates = true;
+
+                            String::new()
+                        }
+                    };
+
+                    if !clause.is_empty() {
+                        if !page_sql.is_empty() {
+                            page_sql.push(' ');
+                        }
+
+                        page_sql.push_str(&clause);
+                    }
+
+                    (page_sql, page_values)
+                }
+                Sql::Predicate(p) => match self.compose_predicate(p, i)? {
+                    Some((body_sql, body_values)) => {
+                        let body_sql = group_or_conditions(body_sql);
+
+                        match where_keyword.take() {
+                            Some(w) => (format!("{} {}", w, body_sql), body_values),
+                            None => {
+                                if p.item.conjunction.eq_ignore_ascii_case("AND") {
+                                    group_where_conditions(&mut sql);
+                                }
+
+                                (format!("{} {}", p.item.conjunction, body_sql), body_values)
+                            }
+                        }
+                    }
+                    None => {
+                        where_predicates = true;
+
+                        continue;
+                    }
+                },
             };
 
             if sub_sql.len() == 0 {
                 continue;
             }
 
+            let mut sub_sql = sub_sql;
+
+            if let Some(w) = where_keyword.take() {
+                // once every predicate following the WHERE has been left out, the condition after
+                // them becomes the first and loses its conjunction, or the WHERE is dropped when
+                // no condition follows
+                let first = if let Sql::Ending(_) = c {
+                    None
+                }
+                else if where_predicates {
+                    strip_conjunction(&sub_sql).map(|rest| rest.to_string())
+                }
+                else {
+                    Some(sub_sql.clone())
+                };
+
+                match first {
+                    Some(first) if first.is_empty() => {
+                        where_keyword = Some(w);
+                        where_predicates = false;
+
+                        continue;
+                    }
+                    Some(first) => {
+                        if !sql.is_empty() {
+                            sql.push(' ');
+                        }
+
+                        sql.push_str(&w);
+
+                        sub_sql = first;
+                    }
+                    None => {}
+                }
+            }
+
             if sub_sql == "," {
                 skip_this = true;
             }
@@ -227,26 +1002,254 @@ pub trait Composer: Sized {
         Ok((sql, values))
     }
 
-    fn compose_command<'c>(
-        &self,
-        composition: &ParsedItem<SqlComposition>,
-        offset: usize,
-        child: bool,
-    ) -> Result<(String, Vec<Self::Value>), ()> {
-        match &composition.item.command {
-            Some(s) => {
-                match s.item().to_lowercase().as_str() {
-                    "compose" => {
-                        let mut out = composition.clone();
+    /// The items of sql with each `:if()` replaced by the items of its chosen branch.
+    fn expand_conditionals<'s>(&self, sql: &'s [Sql]) -> Vec<&'s Sql> {
+        let mut items = vec![];
 
-                        out.item.command = None;
+        for c in sql {
+            match c {
+                Sql::Conditional(cond) => {
+                    let branch = match self.get_values(cond.item.name.to_string()) {
+                        Some(v) if !v.is_empty() => &cond.item.sql,
+                        _ => &cond.item.else_sql,
+                    };
 
-                        match &out.item.of[0].item().path() {
-                            Some(path) => match self
-                                .mock_values()
-                                .get(&SqlCompositionAlias::Path(path.into()))
+                    items.extend(self.expand_conditionals(branch));
+                }
+                _ => items.push(c),
+            }
+        }
+
+        items
+    }
+
+    pub fn compose_predicate(
+        &self,
+        predicate: &ParsedItem<SqlPredicate>,
+        offset: usize,
+    ) -> Result<Option<Composed<C::Value>>, ()> {
+        for name in predicate.item.binding_names() {
+            match self.get_values(name) {
+                Some(v) if !v.is_empty() => {}
+
+                _ => return Ok(None),
+            }
+        }
+
+        let item = ParsedItem::generated(
+            SqlComposition {
+                sql: predicate.item.sql.clone(),
+                ..Default::default()
+            },
+            Some(predicate.item.conjunction.to_string()),
+        )
+        .unwrap();
+
+        self.compose_statement(&item, offset, true).map(Some)
+    }
+
+    pub fn compose_identifier(&self, ident: &SqlIdentifier) -> Result<String, ()> {
+        let chosen = self.bound_text(ident.name.to_string())?;
+
+        if !ident.is_allowed(&chosen) {
+            //TODO: useful error
+            return Err(());
+        }
+
+        Ok(self.composer.identifier_quoting().quote(&chosen))
+    }
+
+    pub fn compose_order_by(&self, order: &SqlOrderBy) -> Result<String, ()> {
+        let bound = self.bound_texts(order.name.to_string())?;
+
+        let terms = if !bound.is_empty() {
+            for term in &bound {
+                let (column, _direction) = split_order_term(term)?;
+
+                if !order.is_allowed(column) {
+                    //TODO: useful error
+                    return Err(());
+                }
+            }
+
+            bound
+        }
+        else {
+            order.default.clone()
+        };
+
+        if terms.is_empty() {
+            return Ok(String::new());
+        }
+
+        let quoting = self.composer.identifier_quoting();
+
+        let mut sql = String::from("ORDER BY ");
+
+        for (i, term) in terms.iter().enumerate() {
+            let (column, direction) = split_order_term(term)?;
+
+            if i > 0 {
+                sql.push_str(", ");
+            }
+
+            sql.push_str(&quoting.quote(column));
+
+            if let Some(d) = direction {
+                sql.push(' ');
+                sql.push_str(&d);
+            }
+        }
+
+        Ok(sql)
+    }
+
+    /// Returns the keyset predicate, if the cursor is bound, the clause limiting the rows and the
+    /// values for both, in that order.
+    pub fn compose_pagination(
+        &self,
+        pagination: &SqlPagination,
+        offset: usize,
+    ) -> Result<ComposedPagination<C::Value>, ()> {
+        let mut values: Vec<C::Value> = vec![];
+
+        let is_bound = |name: &Option<String>| match name {
+            Some(n) => match self.get_values(n.to_string()) {
+                Some(v) => !v.is_empty(),
+                None => false,
+            },
+            None => false,
+        };
+
+        let single_binding = |name: &Option<String>| {
+            SqlBinding::new(name.clone().unwrap(), false, Some(1), Some(1), false).unwrap()
+        };
+
+        let predicate = match &pagination.cursor_column {
+            Some(column) if is_bound(&pagination.cursor) => {
+                let (cursor_sql, cursor_values) =
+                    self.compose_binding(single_binding(&pagination.cursor), offset)?;
+
+                values.extend(cursor_values);
+
+                Some(format!(
+                    "{} > {}",
+                    self.composer.identifier_quoting().quote(column),
+                    cursor_sql
+                ))
+            }
+            _ => None,
+        };
+
+        let mut clause: Vec<String> = vec![];
+
+        if let Some(column) = &pagination.cursor_column {
+            clause.push(format!(
+                "ORDER BY {}",
+                self.composer.identifier_quoting().quote(column)
+            ));
+        }
+
+        let has_limit = is_bound(&pagination.limit);
+        let has_offset = is_bound(&pagination.offset);
+
+        match self.composer.pagination_style() {
+            PaginationStyle::LimitOffset => {
+                if has_limit {
+                    let (limit_sql, limit_values) = self.compose_binding(
+                        single_binding(&pagination.limit),
+                        offset + values.len(),
+                    )?;
+
+                    values.extend(limit_values);
+                    clause.push(format!("LIMIT {}", limit_sql));
+                }
+                else if has_offset {
+                    if let Some(limit) = self.composer.unbounded_limit() {
+                        clause.push(format!("LIMIT {}", limit));
+                    }
+                }
+
+                if has_offset {
+                    let (offset_sql, offset_values) = self.compose_binding(
+                        single_binding(&pagination.offset),
+                        offset + values.len(),
+                    )?;
+
+                    values.extend(offset_values);
+                    clause.push(format!("OFFSET {}", offset_sql));
+                }
+            }
+            PaginationStyle::OffsetFetch => {
+                if has_offset {
+                    let (offset_sql, offset_values) = self.compose_binding(
+                        single_binding(&pagination.offset),
+                        offset + values.len(),
+                    )?;
+
+                    values.extend(offset_values);
+                    clause.push(format!("OFFSET {} ROWS", offset_sql));
+                }
+                else if has_limit {
+                    clause.push("OFFSET 0 ROWS".to_string());
+                }
+
+                if has_limit {
+                    let (limit_sql, limit_values) = self.compose_binding(
+                        single_binding(&pagination.limit),
+                        offset + values.len(),
+                    )?;
+
+                    values.extend(limit_values);
+                    clause.push(format!("FETCH NEXT {} ROWS ONLY", limit_sql));
+                }
+            }
+        }
+
+        let clause = clause.join(" ");
+
+        Ok((predicate, clause, values))
+    }
+
+    pub fn bound_text(&self, name: String) -> Result<String, ()> {
+        let mut texts = self.bound_texts(name)?;
+
+        if texts.len() == 1 {
+            Ok(texts.remove(0))
+        }
+        else {
+            //TODO: useful error
+            Err(())
+        }
+    }
+
+    pub fn bound_texts(&self, name: String) -> Result<Vec<String>, ()> {
+        match self.get_values(name) {
+            Some(v) => v.iter().map(|value| self.composer.value_text(value)).collect(),
+            None => Ok(vec![]),
+        }
+    }
+
+    pub fn compose_command(
+        &self,
+        composition: &ParsedItem<SqlComposition>,
+        offset: usize,
+        child: bool,
+    ) -> Result<(String, Vec<C::Value>), ()> {
+        match &composition.item.command {
+            Some(s) => {
+                match s.item().to_lowercase().as_str() {
+                    "compose" => {
+                        let mut out = composition.clone();
+
+                        out.item.command = None;
+
+                        match &out.item.of[0].item().path() {
+                            Some(path) => match self
+                                .mocks
+                                .get(&SqlCompositionAlias::Path(path.into()))
                             {
-                                Some(e) => Ok(self.mock_compose(e, offset)),
+                                Some(e) => self.mock_compose(e, offset),
                                 None => self.compose_statement(
                                     &out.item.aliases.get(&out.item.of[0].item()).unwrap(),
                                     offset,
@@ -262,27 +1265,37 @@ pub trait Composer: Sized {
                     }
                     "count" => self.compose_count_command(composition, offset, child),
                     "union" => self.compose_union_command(composition, offset, child),
-                    // TODO: handle this error better
-                    _ => panic!("unknown call"),
+                    "with" => self.compose_with_command(composition, offset, child),
+                    "exists" => self.compose_exists_command(composition, offset, child, false),
+                    "not_exists" => self.compose_exists_command(composition, offset, child, true),
+                    name => match self.composer.get_macro(name.to_string()) {
+                        Some(handler) => {
+                            let (out, macro_values) = handler.expand(&composition.item)?;
+
+                            if !macro_values.is_empty() {
+                                //TODO: useful error "values bound by {} need expand_macros()", name
+                                return Err(());
+                            }
+
+                            let item = ParsedItem::generated(out, Some(name.to_uppercase())).unwrap();
+
+                            self.compose_statement(&item, offset, child)
+                        }
+                        //TODO: useful error "unknown command {}", name
+                        None => Err(()),
+                    },
                 }
             }
             None => self.compose_statement(&composition, offset, child),
         }
     }
 
-    fn compose_count_command(
+    pub fn compose_count_command(
         &self,
         composition: &ParsedItem<SqlComposition>,
         offset: usize,
         child: bool,
-    ) -> Result<(String, Vec<Self::Value>), ()>;
-
-    fn compose_count_default_command(
-        &self,
-        composition: &ParsedItem<SqlComposition>,
-        offset: usize,
-        child: bool,
-    ) -> Result<(String, Vec<Self::Value>), ()> {
+    ) -> Result<(String, Vec<C::Value>), ()> {
         let mut out = SqlComposition::default();
 
         let mut select = String::from("SELECT COUNT(");
@@ -313,8 +1326,11 @@ pub trait Composer: Sized {
                 }
             }
 
-            out.push_generated_literal(") AS count_main", Some("COUNT".into()))
-                .unwrap();
+            out.push_generated_literal(
+                &format!(") {}", self.composer.table_alias("count_main")),
+                Some("COUNT".into()),
+            )
+            .unwrap();
         }
 
         out.push_generated_end(Some("COUNT".into())).unwrap();
@@ -324,19 +1340,12 @@ pub trait Composer: Sized {
         self.compose_statement(&item, offset, child)
     }
 
-    fn compose_union_command(
-        &self,
-        composition: &ParsedItem<SqlComposition>,
-        offset: usize,
-        child: bool,
-    ) -> Result<(String, Vec<Self::Value>), ()>;
-
-    fn compose_union_default_command(
+    pub fn compose_union_command(
         &self,
         composition: &ParsedItem<SqlComposition>,
         offset: usize,
         child: bool,
-    ) -> Result<(String, Vec<Self::Value>), ()> {
+    ) -> Result<(String, Vec<C::Value>), ()> {
         let mut out = SqlComposition::default();
 
         // columns in this case would mean an compose on each side of the union literal
@@ -373,91 +1382,268 @@ pub trait Composer: Sized {
         self.compose_statement(&item, offset, child)
     }
 
-    fn compose_binding(
+    pub fn compose_with_command(
+        &self,
+        composition: &ParsedItem<SqlComposition>,
+        offset: usize,
+        child: bool,
+    ) -> Result<(String, Vec<C::Value>), ()> {
+        let mut out = SqlComposition::default();
+
+        let names = match &composition.item.columns {
+            Some(n) if n.len() == composition.item.of.len() => n,
+            //TODO: useful error
+            _ => return Err(()),
+        };
+
+        out.push_generated_literal("WITH", Some("WITH".into()))
+            .unwrap();
+
+        for (i, (name, position)) in names.iter().zip(composition.item.of.iter()).enumerate() {
+            if i > 0 {
+                out.push_generated_literal(",", Some("WITH".into()))
+                    .unwrap();
+            }
+
+            out.push_generated_literal(&format!("{} AS (", name.item), Some("WITH".into()))
+                .unwrap();
+
+            // composed through the compose command so that mocked paths are honored
+            out.push_generated_compose(position, &composition.item.aliases, Some("WITH".into()))
+                .unwrap();
+
+            out.push_generated_literal(")", Some("WITH".into()))
+                .unwrap();
+        }
+
+        // a template starting with :with() is parsed into the :with() composition itself, so the
+        // statement following it is held in its sql
+        for sql in composition.item.sql.iter() {
+            out.push_sql(sql.clone()).unwrap();
+        }
+
+        let item = ParsedItem::generated(out, Some("WITH".into())).unwrap();
+
+        self.compose_statement(&item, offset, child)
+    }
+
+    pub fn compose_exists_command(
+        &self,
+        composition: &ParsedItem<SqlComposition>,
+        offset: usize,
+        child: bool,
+        negated: bool,
+    ) -> Result<(String, Vec<C::Value>), ()> {
+        let command = if negated { "NOT_EXISTS" } else { "EXISTS" };
+
+        let mut out = SqlComposition::default();
+
+        if composition.item.of.len() != 1 {
+            //TODO: useful error
+            return Err(());
+        }
+
+        let mut select = String::new();
+
+        if negated {
+            select.push_str("NOT ");
+        }
+
+        select.push_str("EXISTS (SELECT 1 FROM (");
+
+        out.push_generated_literal(&select, Some(command.into()))
+            .unwrap();
+
+        // composed through the compose command so that mocked paths are honored
+        out.push_generated_compose(
+            &composition.item.of[0],
+            &composition.item.aliases,
+            Some(command.into()),
+        )
+        .unwrap();
+
+        out.push_generated_literal(
+            &format!(") {})", self.composer.table_alias("exists_main")),
+            Some(command.into()),
+        )
+        .unwrap();
+
+        let item = ParsedItem::generated(out, Some(command.into())).unwrap();
+
+        self.compose_statement(&item, offset, child)
+    }
+
+    pub fn compose_binding(
         &self,
         binding: SqlBinding,
         offset: usize,
-    ) -> Result<(String, Vec<Self::Value>), ()> {
+    ) -> Result<(String, Vec<C::Value>), ()> {
+        if binding.columns.is_some() {
+            return self.compose_row_binding(binding, offset);
+        }
+
         let name = &binding.name;
         let mut sql = String::new();
         let mut new_values = vec![];
 
-        let i = offset;
+        let v = match self.get_values(name.to_string()) {
+            Some(v) => v,
+            //TODO: useful error "no value for binding {} of {}", offset, name
+            None => return Err(()),
+        };
+
+        if v.is_empty() && binding.nullable {
+            sql.push_str("NULL");
 
-        match self.get_values(name.to_string()) {
-            Some(v) => {
-                let mut found = 0;
+            return Ok((sql, new_values));
+        }
 
-                for iv in v.iter() {
-                    if new_values.len() > 0 {
-                        sql.push_str(", ");
-                    }
+        self.check_binding_values(&binding, v.len())?;
 
-                    sql.push_str(&self.binding_tag(new_values.len() + offset, name.to_string()));
+        if v.len() == 1 {
+            if let Some(tag) = self.reuse_binding_tag(offset, name) {
+                return Ok((tag, new_values));
+            }
+        }
 
-                    new_values.push(*iv);
+        for (found, iv) in v.iter().enumerate() {
+            if found > 0 {
+                sql.push_str(", ");
+            }
 
-                    found += 1;
-                }
+            let (value_sql, value_values) =
+                self.compose_value(new_values.len() + offset, name.to_string(), iv)?;
 
-                if found == 0 {
-                    if binding.nullable {
-                        sql.push_str("NULL");
+            sql.push_str(&value_sql);
 
-                        return Ok((sql, new_values));
-                    }
-                    else {
-                        return Err(());
-                    }
-                }
+            new_values.extend(value_values);
+        }
 
-                if let Some(min) = binding.min_values {
-                    if found < min {
-                        //TODO: useful error
-                        return Err(());
-                    }
-                }
+        Ok((sql, new_values))
+    }
 
-                if let Some(max) = binding.max_values {
-                    if found > max {
-                        //TODO: useful error
-                        return Err(());
-                    }
-                }
-                else {
-                    if binding.min_values.is_none() && found > 1 {
-                        //TODO: useful error
-                        return Err(());
-                    }
-                }
+    /// Checks the number of values bound to a binding against what it is EXPECTING, a single
+    /// value when it doesn't say.
+    pub fn check_binding_values(&self, binding: &SqlBinding, found: usize) -> Result<(), ()> {
+        let found = found as u32;
+
+        if found == 0 {
+            //TODO: useful error
+            return Err(());
+        }
+
+        if let Some(min) = binding.min_values {
+            if found < min {
+                //TODO: useful error
+                return Err(());
             }
-            //TODO: error "no value for binding {} of {}", i, name),
-            //only error that isn't
-            None => panic!("no value for binding {} of {}", i, name),
-        };
+        }
 
-        Ok((sql, new_values))
+        if let Some(max) = binding.max_values {
+            if found > max {
+                //TODO: useful error
+                return Err(());
+            }
+        }
+        else {
+            if binding.min_values.is_none() && found > 1 {
+                //TODO: useful error
+                return Err(());
+            }
+        }
+
+        Ok(())
     }
 
-    fn binding_tag(&self, u: usize, name: String) -> String;
+    pub fn compose_row_binding(
+        &self,
+        binding: SqlBinding,
+        offset: usize,
+    ) -> Result<(String, Vec<C::Value>), ()> {
+        let name = &binding.name;
 
-    fn get_values(&self, name: String) -> Option<&Vec<Self::Value>>;
+        let width = match &binding.columns {
+            Some(c) if !c.is_empty() => c.len(),
+            //TODO: useful error
+            _ => return Err(()),
+        };
 
-    fn insert_value(&mut self, name: String, values: Vec<Self::Value>) -> ();
+        let values = match self.get_values(name.to_string()) {
+            Some(v) => v,
+            //TODO: useful error
+            None => return Err(()),
+        };
 
-    fn config() -> ComposerConfig;
+        if values.len() % width != 0 {
+            //TODO: useful error
+            return Err(());
+        }
+
+        let rows = (values.len() / width) as u32;
+
+        if rows < binding.min_values.unwrap_or(1) {
+            //TODO: useful error
+            return Err(());
+        }
+
+        if let Some(max) = binding.max_values {
+            if rows > max {
+                //TODO: useful error
+                return Err(());
+            }
+        }
+
+        let mut sql = String::new();
+        let mut new_values = vec![];
+
+        for (r, row) in values.chunks(width).enumerate() {
+            if r > 0 {
+                sql.push_str(", ");
+            }
+
+            sql.push('(');
+
+            for (c, value) in row.iter().enumerate() {
+                if c > 0 {
+                    sql.push_str(", ");
+                }
+
+                let (value_sql, value_values) =
+                    self.compose_value(new_values.len() + offset, name.to_string(), value)?;
 
-    fn root_mock_values(&self) -> &Vec<BTreeMap<String, Self::Value>>;
+                sql.push_str(&value_sql);
 
-    fn mock_values(&self) -> &HashMap<SqlCompositionAlias, Vec<BTreeMap<String, Self::Value>>>;
+                new_values.extend(value_values);
+            }
+
+            sql.push(')');
+        }
+
+        Ok((sql, new_values))
+    }
 
-    fn mock_compose(
+    pub fn compose_array_binding(
         &self,
-        mock_values: &Vec<BTreeMap<String, Self::Value>>,
+        binding: &SqlBinding,
         offset: usize,
-    ) -> (String, Vec<Self::Value>) {
+    ) -> Result<(String, Vec<C::Value>), ()> {
+        match self.get_values(binding.name.to_string()) {
+            Some(v) if v.len() == 1 => Ok((
+                self.binding_tag(offset, binding.name.to_string()),
+                vec![v[0].clone()],
+            )),
+            //TODO: useful error
+            _ => Err(()),
+        }
+    }
+
+    pub fn mock_compose(
+        &self,
+        mock_values: &[BTreeMap<String, C::Value>],
+        offset: usize,
+    ) -> Result<(String, Vec<C::Value>), ()> {
         let mut sql = String::new();
-        let mut values: Vec<Self::Value> = vec![];
+        let mut values: Vec<C::Value> = vec![];
 
         let mut i = offset;
         let mut r = 0;
@@ -470,7 +1656,8 @@ pub trait Composer: Sized {
         let mut expected_columns: Option<u8> = None;
 
         if mock_values.is_empty() {
-            panic!("mock_values cannot be empty");
+            //TODO: useful error "mock_values cannot be empty"
+            return Err(());
         }
         else {
             for row in mock_values.iter() {
@@ -487,17 +1674,24 @@ pub trait Composer: Sized {
                         sql.push_str(", ")
                     }
 
-                    sql.push_str(&self.binding_tag(i, name.to_string()));
+                    let (value_sql, value_values) = self.compose_value(i, name.to_string(), value)?;
+
+                    sql.push_str(&value_sql);
                     sql.push_str(&format!(" AS {}", &name));
 
-                    values.push(*value);
+                    i += value_values.len();
 
-                    i += 1;
+                    values.extend(value_values);
+                }
+
+                if let Some(dummy) = self.composer.dummy_table() {
+                    sql.push_str(&format!(" FROM {}", dummy));
                 }
 
                 if let Some(ec) = expected_columns {
                     if c != ec {
-                        panic!("expected {} columns found {} for row {}", ec, c, r);
+                        //TODO: useful error "expected {} columns found {} for row {}", ec, c, r
+                        return Err(());
                     }
                 }
                 else {
@@ -509,6 +1703,412 @@ pub trait Composer: Sized {
             }
         }
 
-        (sql, values)
+        Ok((sql, values))
+    }
+
+    fn get_values(&self, name: String) -> Option<&Vec<C::Value>> {
+        self.values.get(&name)
+    }
+
+    fn compose_value(
+        &self,
+        u: usize,
+        name: String,
+        value: &C::Value,
+    ) -> Result<(String, Vec<C::Value>), ()> {
+        self.composer
+            .compose_value(u, name, value, &mut self.tags.borrow_mut())
+    }
+
+    fn binding_tag(&self, u: usize, name: String) -> String {
+        self.composer
+            .binding_tag(u, name, &mut self.tags.borrow_mut())
+    }
+
+    fn reuse_binding_tag(&self, u: usize, name: &str) -> Option<String> {
+        self.composer
+            .reuse_binding_tag(u, name, &mut self.tags.borrow_mut())
+    }
+}
+
+/// Finds a trailing `[NOT] IN (` in sql, returning the length of the sql before it and whether it
+/// was negated.
+fn split_in_list(sql: &str) -> Option<(usize, bool)> {
+    let trimmed = sql.trim_end();
+
+    if !trimmed.ends_with('(') {
+        return None;
+    }
+
+    let before = trimmed[..trimmed.len() - 1].trim_end();
+
+    let len = before.len();
+
+    if len < 3 || !before.is_char_boundary(len - 2) || !before[len - 2..].eq_ignore_ascii_case("IN") {
+        return None;
+    }
+
+    let head = &before[..len - 2];
+
+    if !head.ends_with(char::is_whitespace) {
+        return None;
+    }
+
+    let head = head.trim_end();
+    let len = head.len();
+
+    if len > 3
+        && head.is_char_boundary(len - 3)
+        && head[len - 3..].eq_ignore_ascii_case("NOT")
+        && head[..len - 3].ends_with(char::is_whitespace)
+    {
+        Some((head[..len - 3].trim_end().len(), true))
+    }
+    else {
+        Some((len, false))
+    }
+}
+
+/// `s` without its ORDER BY, or anything following it but the ending, for counting its rows from
+/// a derived table, where most databases don't allow an ORDER BY.
+fn without_order_by(s: &SqlComposition) -> SqlComposition {
+    let mut out = s.clone();
+
+    let mut depth = 0;
+    let mut ordered = false;
+
+    out.sql = vec![];
+
+    for c in &s.sql {
+        if ordered {
+            if let Sql::Ending(_) = c {
+                out.sql.push(c.clone());
+            }
+
+            continue;
+        }
+
+        match c {
+            Sql::OrderBy(_) | Sql::Pagination(_) => ordered = true,
+            Sql::Literal(t) => match find_order_by(&t.item.value, &mut depth) {
+                Some(at) => {
+                    let mut literal = t.clone();
+
+                    literal.item.value = t.item.value[..at].trim_end().to_string();
+
+                    if !literal.item.value.is_empty() {
+                        out.sql.push(Sql::Literal(literal));
+                    }
+
+                    ordered = true;
+                }
+                None => out.sql.push(c.clone()),
+            },
+            _ => out.sql.push(c.clone()),
+        }
     }
+
+    out
+}
+
+/// Tracks the paren depth through a literal, returning where an ORDER BY outside of parens starts.
+fn find_order_by(sql: &str, depth: &mut i32) -> Option<usize> {
+    let mut word = String::new();
+    let mut start = 0;
+    let mut order: Option<usize> = None;
+
+    for (i, c) in sql.char_indices().chain(std::iter::once((sql.len(), ' '))) {
+        if c.is_alphanumeric() || c == '_' {
+            if word.is_empty() {
+                start = i;
+            }
+
+            word.push(c);
+            continue;
+        }
+
+        if !word.is_empty() {
+            if *depth == 0 && order.is_some() && word.eq_ignore_ascii_case("BY") {
+                return order;
+            }
+
+            order = if *depth == 0 && word.eq_ignore_ascii_case("ORDER") {
+                Some(start)
+            }
+            else {
+                None
+            };
+        }
+
+        word.clear();
+
+        match c {
+            '(' => *depth += 1,
+            ')' => *depth -= 1,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Tracks the paren depth through a literal, returning true if it has a WHERE outside of parens.
+fn scan_where_keyword(sql: &str, depth: &mut i32) -> bool {
+    let mut found = false;
+    let mut word = String::new();
+
+    for c in sql.chars().chain(std::iter::once(' ')) {
+        if c.is_alphanumeric() || c == '_' {
+            word.push(c);
+            continue;
+        }
+
+        if *depth == 0 && word.eq_ignore_ascii_case("WHERE") {
+            found = true;
+        }
+
+        word.clear();
+
+        match c {
+            '(' => *depth += 1,
+            ')' => *depth -= 1,
+            _ => {}
+        }
+    }
+
+    found
+}
+
+/// Wraps the conditions following the last WHERE or HAVING of sql in parens when they hold an OR
+/// outside of parens, so a condition ANDed onto them applies to all of them.
+fn group_where_conditions(sql: &mut String) {
+    let words = top_level_words(sql);
+
+    let start = match words.iter().rev().find(|(s, e)| {
+        let word = &sql[*s..*e];
+
+        word.eq_ignore_ascii_case("WHERE") || word.eq_ignore_ascii_case("HAVING")
+    }) {
+        Some((_, e)) => *e,
+        None => return,
+    };
+
+    let has_or = words
+        .iter()
+        .any(|(s, e)| *s >= start && sql[*s..*e].eq_ignore_ascii_case("OR"));
+
+    if !has_or {
+        return;
+    }
+
+    let conditions = sql[start..].trim().to_string();
+
+    sql.truncate(start);
+    sql.push_str(&format!(" ({})", conditions));
+}
+
+/// Wraps sql in parens when it holds an OR outside of parens.
+fn group_or_conditions(sql: String) -> String {
+    let has_or = top_level_words(&sql)
+        .iter()
+        .any(|(s, e)| sql[*s..*e].eq_ignore_ascii_case("OR"));
+
+    if has_or {
+        format!("({})", sql)
+    }
+    else {
+        sql
+    }
+}
+
+/// The start and end of each word of sql outside of parens and quotes.
+fn top_level_words(sql: &str) -> Vec<(usize, usize)> {
+    let mut words = vec![];
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    let mut start: Option<usize> = None;
+
+    for (i, c) in sql.char_indices().chain(std::iter::once((sql.len(), ' '))) {
+        if let Some(q) = quote {
+            if c == q {
+                quote = None;
+            }
+
+            continue;
+        }
+
+        if c.is_alphanumeric() || c == '_' {
+            if start.is_none() {
+                start = Some(i);
+            }
+
+            continue;
+        }
+
+        if let Some(s) = start.take() {
+            if depth == 0 {
+                words.push((s, i));
+            }
+        }
+
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            '\'' | '"' | '`' | '[' => quote = Some(if c == '[' { ']' } else { c }),
+            _ => {}
+        }
+    }
+
+    words
+}
+
+/// Splits a trailing WHERE off of a literal, returning the remaining sql and the keyword.
+fn split_where_keyword(sql: &str) -> Option<(&str, &str)> {
+    let len = sql.len();
+
+    if len < 5 || !sql.is_char_boundary(len - 5) {
+        return None;
+    }
+
+    let (head, keyword) = sql.split_at(len - 5);
+
+    if !keyword.eq_ignore_ascii_case("WHERE") {
+        return None;
+    }
+
+    match head.chars().last() {
+        None => Some((head, keyword)),
+        Some(c) if c.is_whitespace() || c == ')' => Some((head.trim_end(), keyword)),
+        _ => None,
+    }
+}
+
+/// The sql following a leading AND or OR, or None when it doesn't start with a conjunction.
+fn strip_conjunction(sql: &str) -> Option<&str> {
+    let trimmed = sql.trim_start();
+
+    for conjunction in &["AND", "OR"] {
+        let len = conjunction.len();
+
+        if trimmed.len() < len || !trimmed.is_char_boundary(len) {
+            continue;
+        }
+
+        let (head, rest) = trimmed.split_at(len);
+
+        if !head.eq_ignore_ascii_case(conjunction) {
+            continue;
+        }
+
+        match rest.chars().next() {
+            None => return Some(rest),
+            Some(c) if c.is_whitespace() || c == '(' => return Some(rest.trim_start()),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// The commands composers handle themselves, never given to a `MacroHandler`.
+const COMMANDS: [&str; 6] = ["compose", "count", "union", "with", "exists", "not_exists"];
+
+/// Replaces the macro commands of `s` and of the compositions it includes with what their
+/// handlers return, collecting the values the handlers bind into `macro_values`.
+fn expand_composition<C: Composer>(
+    composer: &C,
+    s: &SqlComposition,
+    values: &Binds<C::Value>,
+    macro_values: &mut Binds<Value>,
+) -> Result<SqlComposition, ()> {
+    if let Some(command) = &s.command {
+        let name = command.item().to_lowercase();
+
+        if !COMMANDS.contains(&name.as_str()) {
+            //TODO: useful error "unknown command {}", name
+            let handler = composer.get_macro(name).ok_or(())?;
+
+            let (out, bound) = handler.expand(s)?;
+
+            for (name, v) in bound {
+                let conflict = match macro_values.get(&name) {
+                    Some(existing) => *existing != v,
+                    None => values.contains_key(&name),
+                };
+
+                if conflict {
+                    //TODO: useful error "{} is already bound", name
+                    return Err(());
+                }
+
+                macro_values.insert(name, v);
+            }
+
+            return expand_composition(composer, &out, values, macro_values);
+        }
+    }
+
+    let mut out = s.clone();
+
+    out.sql = expand_sql(composer, &s.sql, values, macro_values)?;
+
+    for alias in out.aliases.values_mut() {
+        alias.item = expand_composition(composer, &alias.item, values, macro_values)?;
+    }
+
+    Ok(out)
+}
+
+fn expand_sql<C: Composer>(
+    composer: &C,
+    sql: &[Sql],
+    values: &Binds<C::Value>,
+    macro_values: &mut Binds<Value>,
+) -> Result<Vec<Sql>, ()> {
+    let mut out = vec![];
+
+    for c in sql {
+        let mut c = c.clone();
+
+        match &mut c {
+            Sql::Composition((sc, _)) => {
+                sc.item = expand_composition(composer, &sc.item, values, macro_values)?;
+            }
+            Sql::Conditional(cond) => {
+                cond.item.sql = expand_sql(composer, &cond.item.sql, values, macro_values)?;
+                cond.item.else_sql = expand_sql(composer, &cond.item.else_sql, values, macro_values)?;
+            }
+            Sql::Predicate(p) => {
+                p.item.sql = expand_sql(composer, &p.item.sql, values, macro_values)?;
+            }
+            _ => {}
+        }
+
+        out.push(c);
+    }
+
+    Ok(out)
+}
+
+/// Splits a `column [ASC|DESC]` sort term, normalizing the direction to upper case.
+fn split_order_term(term: &str) -> Result<(&str, Option<String>), ()> {
+    let mut parts = term.split_whitespace();
+
+    let column = parts.next().ok_or(())?;
+
+    let direction = match parts.next() {
+        Some(d) => match d.to_uppercase().as_str() {
+            "ASC" => Some("ASC".to_string()),
+            "DESC" => Some("DESC".to_string()),
+            //TODO: useful error
+            _ => return Err(()),
+        },
+        None => None,
+    };
+
+    if parts.next().is_some() {
+        return Err(());
+    }
+
+    Ok((column, direction))
 }
@@ -0,0 +1,473 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use super::{Binds, BindingTags, Composed, Composer, ComposerConfig, ComposerContext,
+            IdentifierQuoting, MacroHandler, Mocks, PaginationStyle, placeholder_name};
+
+use crate::types::SqlComposition;
+
+use crate::types::value::{LiteralStyle, ToValue, Value};
+
+/// How a dialect writes the placeholder for a bound value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlaceholderStyle {
+    /// `?`, as used by ODBC and JDBC
+    Question,
+    /// `?1`, as used by SQLite
+    NumberedQuestion,
+    /// `$1`, as used by PostgreSQL
+    Dollar,
+    /// `:1`, as used by Oracle
+    Colon,
+    /// `@p1`, as used by SQL Server
+    AtP,
+    /// `:name`, with each value of a multi-valued binding numbered as `:name_1`
+    Named,
+}
+
+impl PlaceholderStyle {
+    /// The placeholder for the value at position `u`, bound to `name`.
+    pub fn tag(&self, u: usize, name: &str) -> String {
+        match self {
+            PlaceholderStyle::Question => "?".to_string(),
+            PlaceholderStyle::NumberedQuestion => format!("?{}", u),
+            PlaceholderStyle::Dollar => format!("${}", u),
+            PlaceholderStyle::Colon => format!(":{}", u),
+            PlaceholderStyle::AtP => format!("@p{}", u),
+            PlaceholderStyle::Named => format!(":{}", placeholder_name(name)),
+        }
+    }
+}
+
+/// Composes SQL for a dialect without a driver, described by its placeholder style, identifier
+/// quoting and pagination.
+pub struct DialectComposer<'a> {
+    #[allow(dead_code)]
+    config:             ComposerConfig,
+    pub macros:         HashMap<String, Box<dyn MacroHandler>>,
+    placeholder_style:  PlaceholderStyle,
+    identifier_quoting: IdentifierQuoting,
+    pagination_style:   PaginationStyle,
+    unbounded_limit:    Option<String>,
+    table_alias_as:     bool,
+    dummy_table:        Option<String>,
+    literal_style:      LiteralStyle,
+    max_bind_params:    usize,
+    values:             PhantomData<&'a ()>,
+}
+
+impl<'a> DialectComposer<'a> {
+    pub fn new(placeholder_style: PlaceholderStyle) -> Self {
+        Self {
+            config: Self::config(),
+            macros: HashMap::new(),
+            placeholder_style,
+            identifier_quoting: IdentifierQuoting::default(),
+            pagination_style: PaginationStyle::default(),
+            unbounded_limit: None,
+            table_alias_as: true,
+            dummy_table: None,
+            literal_style: LiteralStyle::default(),
+            max_bind_params: 65535,
+            values: PhantomData,
+        }
+    }
+
+    /// `@p1` placeholders, `[name]` identifiers and OFFSET/FETCH pagination, with SQL Server's
+    /// limit of 2100 parameters.
+    pub fn sql_server() -> Self {
+        let mut composer = Self::new(PlaceholderStyle::AtP);
+
+        composer.set_identifier_quoting(IdentifierQuoting::Bracket);
+        composer.set_pagination_style(PaginationStyle::OffsetFetch);
+        composer.set_max_bind_params(2100);
+
+        composer
+    }
+
+    /// `:1` placeholders, `"name"` identifiers and OFFSET/FETCH pagination, with table aliases
+    /// written without AS and mocked rows selected FROM DUAL.
+    pub fn oracle() -> Self {
+        let mut composer = Self::new(PlaceholderStyle::Colon);
+
+        composer.set_pagination_style(PaginationStyle::OffsetFetch);
+        composer.set_table_alias_as(false);
+        composer.set_dummy_table(Some("DUAL".into()));
+
+        composer
+    }
+
+    /// Borrows mocked rows of driver independent values as the values this composer binds.
+    pub fn value_mocks(mocks: &'a Mocks<Value>) -> Mocks<&'a dyn ToValue> {
+        mocks
+            .iter()
+            .map(|(alias, rows)| {
+                (
+                    alias.clone(),
+                    rows.iter()
+                        .map(|row| {
+                            row.iter()
+                                .map(|(name, v)| (name.to_string(), v as &dyn ToValue))
+                                .collect()
+                        })
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    pub fn set_identifier_quoting(&mut self, quoting: IdentifierQuoting) {
+        self.identifier_quoting = quoting;
+    }
+
+    pub fn set_pagination_style(&mut self, style: PaginationStyle) {
+        self.pagination_style = style;
+    }
+
+    /// Sets the LIMIT written ahead of an OFFSET bound without a limit, such as `-1` for SQLite.
+    pub fn set_unbounded_limit(&mut self, limit: Option<String>) {
+        self.unbounded_limit = limit;
+    }
+
+    /// Sets whether a table alias is written after AS, as Oracle only accepts `( ... ) name`.
+    pub fn set_table_alias_as(&mut self, table_alias_as: bool) {
+        self.table_alias_as = table_alias_as;
+    }
+
+    /// Sets the table each row of a mocked table is selected FROM, such as `DUAL` for Oracle.
+    pub fn set_dummy_table(&mut self, table: Option<String>) {
+        self.dummy_table = table;
+    }
+
+    /// Sets how the dialect writes a value as a literal, such as `LiteralStyle::MySql` for its
+    /// backslash escapes.
+    pub fn set_literal_style(&mut self, style: LiteralStyle) {
+        self.literal_style = style;
+    }
+
+    pub fn set_max_bind_params(&mut self, max: usize) {
+        self.max_bind_params = max;
+    }
+
+    /// Composes `s` with `PlaceholderStyle::Named`, pairing each value with its placeholder.
+    pub fn compose_named(
+        &self,
+        s: &SqlComposition,
+        values: &Binds<&'a dyn ToValue>,
+        mocks: &Mocks<&'a dyn ToValue>,
+    ) -> Result<Composed<(String, &'a dyn ToValue)>, ()> {
+        if self.placeholder_style != PlaceholderStyle::Named {
+            //TODO: useful error
+            return Err(());
+        }
+
+        let context = ComposerContext::new(self, values, mocks);
+
+        let (sql, values) = context.compose(s)?;
+
+        let named_values = context.binding_tags().named_values(values)?;
+
+        Ok((sql, named_values))
+    }
+}
+
+impl<'a> Composer for DialectComposer<'a> {
+    type Value = &'a dyn ToValue;
+
+    fn config() -> ComposerConfig {
+        ComposerConfig { start: 0 }
+    }
+
+    fn binding_tag(&self, u: usize, name: String, tags: &mut BindingTags) -> String {
+        if self.placeholder_style != PlaceholderStyle::Named {
+            return self.placeholder_style.tag(u, &name);
+        }
+
+        let tag = tags.named_tag(u, &name);
+
+        self.placeholder_style.tag(u, &tag)
+    }
+
+    /// Only named placeholders are shared, as a name can only be given one value.
+    fn reuse_binding_tag(&self, u: usize, name: &str, tags: &mut BindingTags) -> Option<String> {
+        if self.placeholder_style != PlaceholderStyle::Named {
+            return None;
+        }
+
+        tags.reuse_named_tag(u, name).map(|tag| self.placeholder_style.tag(u, &tag))
+    }
+
+    fn identifier_quoting(&self) -> IdentifierQuoting {
+        self.identifier_quoting
+    }
+
+    fn pagination_style(&self) -> PaginationStyle {
+        self.pagination_style
+    }
+
+    fn unbounded_limit(&self) -> Option<&str> {
+        self.unbounded_limit.as_deref()
+    }
+
+    fn table_alias(&self, alias: &str) -> String {
+        if self.table_alias_as {
+            format!("AS {}", alias)
+        }
+        else {
+            alias.to_string()
+        }
+    }
+
+    fn dummy_table(&self) -> Option<&str> {
+        self.dummy_table.as_deref()
+    }
+
+    fn literal_style(&self) -> LiteralStyle {
+        self.literal_style
+    }
+
+    fn max_bind_params(&self) -> usize {
+        self.max_bind_params
+    }
+
+    fn value_text(&self, value: &Self::Value) -> Result<String, ()> {
+        match value.to_value()? {
+            Value::Text(t) => Ok(t),
+            _ => Err(()),
+        }
+    }
+
+    fn get_macro(&self, name: String) -> Option<&dyn MacroHandler> {
+        self.macros.get(&name).map(|m| m.as_ref())
+    }
+
+    fn insert_macro(&mut self, name: String, handler: Box<dyn MacroHandler>) {
+        self.macros.insert(name.to_lowercase(), handler);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{bind_values, mock_db_object_values};
+
+    use super::{Composer, DialectComposer, PlaceholderStyle, ToValue};
+    use crate::parser::parse_template;
+
+    use crate::types::{Span, SqlCompositionAlias, SqlDbObject, SqlPagination};
+
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_placeholder_styles() {
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1 FROM (:compose(src/tests/values/simple.tql)) AS main WHERE col_1 IN (:bind(col_1_values EXPECTING MIN 1)) AND col_2 = :bind(b);".into()), None).unwrap();
+
+        let styles = vec![
+            (PlaceholderStyle::Question, ["?", "?", "?", "?", "?", "?", "?"]),
+            (PlaceholderStyle::NumberedQuestion, ["?1", "?2", "?3", "?4", "?5", "?6", "?7"]),
+            (PlaceholderStyle::Dollar, ["$1", "$2", "$3", "$4", "$5", "$6", "$7"]),
+            (PlaceholderStyle::Colon, [":1", ":2", ":3", ":4", ":5", ":6", ":7"]),
+            (PlaceholderStyle::AtP, ["@p1", "@p2", "@p3", "@p4", "@p5", "@p6", "@p7"]),
+            (PlaceholderStyle::Named, [":a", ":b", ":c", ":d", ":col_1_values_5", ":col_1_values_6", ":b"]),
+        ];
+
+        for (style, tags) in styles {
+            let composer = DialectComposer::new(style);
+
+            let bind_values = bind_values!(&dyn ToValue:
+            "a" => [&"a_value"],
+            "b" => [&"b_value"],
+            "c" => [&"c_value"],
+            "d" => [&"d_value"],
+            "col_1_values" => [&"a_value", &"c_value"]
+            );
+
+            let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+            let expected_bound_sql = format!("SELECT col_1 FROM ( SELECT {} AS col_1, {} AS col_2, {} AS col_3, {} AS col_4 ) AS main WHERE col_1 IN ( {}, {} ) AND col_2 = {};", tags[0], tags[1], tags[2], tags[3], tags[4], tags[5], tags[6]);
+
+            assert_eq!(bound_sql, expected_bound_sql, "{:?} placeholders", style);
+
+            let expected_len = if style == PlaceholderStyle::Named { 6 } else { 7 };
+
+            assert_eq!(bindings.len(), expected_len, "{:?} values", style);
+        }
+    }
+
+    #[test]
+    fn test_compose_named() {
+        let (_remaining, stmt) = parse_template(
+            Span::new("SELECT * FROM t AS main WHERE a = :bind(a) OR b = :bind(a);".into()),
+            None,
+        )
+        .unwrap();
+
+        let composer = DialectComposer::new(PlaceholderStyle::Named);
+
+        let bind_values = bind_values!(&dyn ToValue: "a" => [&1i64]);
+
+        let (bound_sql, bindings) = composer
+            .compose_named(&stmt.item, &bind_values, &HashMap::new())
+            .expect("compose_named should work");
+
+        assert_eq!(bound_sql, "SELECT * FROM t AS main WHERE a = :a OR b = :a;");
+
+        let names: Vec<&str> = bindings.iter().map(|(name, _)| name.as_str()).collect();
+
+        assert_eq!(names, vec![":a"], "value bound once");
+
+        let composer = DialectComposer::new(PlaceholderStyle::Dollar);
+
+        assert!(composer.compose_named(&stmt.item, &bind_values, &HashMap::new()).is_err(), "requires named placeholders");
+    }
+
+    #[test]
+    fn test_sql_server() {
+        let (_remaining, stmt) = parse_template(Span::new("SELECT :ident(column ALLOW col_1, col_2) FROM t AS main ORDER BY col_1 :paginate(limit, offset);".into()), None).unwrap();
+
+        let composer = DialectComposer::sql_server();
+
+        let bind_values = bind_values!(&dyn ToValue:
+        "column" => [&"col_2"],
+        "limit" => [&10i64],
+        "offset" => [&20i64]
+        );
+
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+        assert_eq!(
+            bound_sql,
+            "SELECT [col_2] FROM t AS main ORDER BY col_1 OFFSET @p1 ROWS FETCH NEXT @p2 ROWS ONLY;"
+        );
+
+        assert_eq!(bindings.len(), 2, "offset and limit");
+        assert_eq!(composer.max_bind_params(), 2100, "sql server parameter limit");
+
+        let composer = DialectComposer::oracle();
+
+        let bind_values = bind_values!(&dyn ToValue:
+        "column" => [&"col_1"],
+        "limit" => [&10i64]
+        );
+
+        let (bound_sql, _bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+        assert_eq!(
+            bound_sql,
+            "SELECT \"col_1\" FROM t AS main ORDER BY col_1 OFFSET 0 ROWS FETCH NEXT :1 ROWS ONLY;"
+        );
+    }
+
+    #[test]
+    fn test_compose_page_offset_fetch() {
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1 FROM t AS main WHERE col_2 = :bind(a) ORDER BY col_1;".into()), None).unwrap();
+
+        let composer = DialectComposer::sql_server();
+
+        let bind_values = bind_values!(&dyn ToValue:
+        "a" => [&"a_value"],
+        "limit" => [&10i64],
+        "offset" => [&20i64]
+        );
+
+        let page = SqlPagination::new("limit".into(), Some("offset".into())).unwrap();
+
+        let ((page_sql, page_bindings), (count_sql, _count_bindings)) = composer
+            .compose_page(&stmt.item, &page, &bind_values, &HashMap::new())
+            .expect("compose_page should work");
+
+        assert_eq!(
+            page_sql,
+            "SELECT col_1 FROM t AS main WHERE col_2 = @p1 ORDER BY col_1 OFFSET @p2 ROWS FETCH NEXT @p3 ROWS ONLY;",
+            "pagination follows the statement's ORDER BY"
+        );
+        assert_eq!(page_bindings.len(), 3, "page values");
+
+        assert_eq!(
+            count_sql,
+            "SELECT COUNT(1) FROM ( SELECT col_1 FROM t AS main WHERE col_2 = @p1 ) AS count_main;",
+            "count without the ORDER BY"
+        );
+    }
+
+    #[test]
+    fn test_oracle_table_alias() {
+        let (_remaining, stmt) = parse_template(Span::new(":count(src/tests/values/simple.tql);".into()), None).unwrap();
+
+        let composer = DialectComposer::oracle();
+
+        let bind_values = bind_values!(&dyn ToValue:
+        "a" => [&"a_value"],
+        "b" => [&"b_value"],
+        "c" => [&"c_value"],
+        "d" => [&"d_value"]
+        );
+
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+        assert_eq!(
+            bound_sql,
+            "SELECT COUNT(1) FROM ( SELECT :1 AS col_1, :2 AS col_2, :3 AS col_3, :4 AS col_4 ) count_main",
+            "count alias without AS"
+        );
+        assert_eq!(bindings.len(), 4, "count values");
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1 FROM main WHERE :exists(src/tests/values/simple.tql);".into()), None).unwrap();
+
+        let mock_values = mock_db_object_values!(&dyn ToValue: "main" => [{"col_1" => &"e_value"}, {"col_1" => &"f_value"}]);
+
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &mock_values).expect("compose should work");
+
+        assert_eq!(
+            bound_sql,
+            "SELECT col_1 FROM ( SELECT :1 AS col_1 FROM DUAL UNION ALL SELECT :2 AS col_1 FROM DUAL ) main WHERE EXISTS (SELECT 1 FROM ( SELECT :3 AS col_1, :4 AS col_2, :5 AS col_3, :6 AS col_4 ) exists_main);",
+            "mocked rows selected from DUAL"
+        );
+        assert_eq!(bindings.len(), 6, "mock and exists values");
+    }
+
+    #[test]
+    fn test_named_dotted_names() {
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1 FROM t AS main WHERE col_1 > :bind(page.after) ORDER BY col_1 :paginate(page.limit);".into()), None).unwrap();
+
+        let composer = DialectComposer::new(PlaceholderStyle::Named);
+
+        let bind_values = bind_values!(&dyn ToValue:
+        "page.after" => [&5i64],
+        "page.limit" => [&10i64]
+        );
+
+        let (bound_sql, bindings) = composer.compose_named(&stmt.item, &bind_values, &HashMap::new()).expect("compose_named should work");
+
+        assert_eq!(
+            bound_sql,
+            "SELECT col_1 FROM t AS main WHERE col_1 > :page__after ORDER BY col_1 LIMIT :page__limit;"
+        );
+
+        let names: Vec<&str> = bindings.iter().map(|(name, _)| name.as_str()).collect();
+
+        assert_eq!(names, vec![":page__after", ":page__limit"], "dotted names written with __");
+    }
+
+    #[test]
+    fn test_named_collisions() {
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1 FROM t AS main WHERE col_1 = :bind(ids_2) AND col_2 IN (:bind(ids EXPECTING MIN 1));".into()), None).unwrap();
+
+        let composer = DialectComposer::new(PlaceholderStyle::Named);
+
+        let bind_values = bind_values!(&dyn ToValue:
+        "ids_2" => [&1i64],
+        "ids" => [&2i64, &3i64]
+        );
+
+        let (bound_sql, bindings) = composer.compose_named(&stmt.item, &bind_values, &HashMap::new()).expect("compose_named should work");
+
+        assert_eq!(
+            bound_sql,
+            "SELECT col_1 FROM t AS main WHERE col_1 = :ids_2 AND col_2 IN ( :ids_2_1, :ids_3 );"
+        );
+
+        let names: Vec<&str> = bindings.iter().map(|(name, _)| name.as_str()).collect();
+
+        assert_eq!(names, vec![":ids_2", ":ids_2_1", ":ids_3"], "each value has its own name");
+    }
+}
@@ -1,113 +1,104 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::HashMap;
+use std::marker::PhantomData;
 
-use super::{Composer, ComposerConfig};
+use super::{BindValue, BindingTags, Composer, ComposerConfig, MacroHandler};
 
-use crate::types::{ParsedItem, SqlBinding, SqlComposition, SqlCompositionAlias};
-
-use crate::types::value::ToValue;
+use crate::types::value::{LiteralStyle, ToValue, Value};
 
 pub struct Connection();
 
+impl<'a, T: ToValue> BindValue<'a, &'a (dyn ToValue + 'a)> for T {
+    fn bind_value(&'a self) -> &'a (dyn ToValue + 'a) {
+        self
+    }
+}
+
 #[derive(Default)]
 pub struct DirectComposer<'a> {
     #[allow(dead_code)]
-    config:           ComposerConfig,
-    values:           BTreeMap<String, Vec<&'a dyn ToValue>>,
-    root_mock_values: Vec<BTreeMap<String, &'a str>>,
-    mock_values:      HashMap<SqlCompositionAlias, Vec<BTreeMap<String, &'a str>>>,
+    config:        ComposerConfig,
+    macros:        HashMap<String, Box<dyn MacroHandler>>,
+    literal_style: LiteralStyle,
+    values:        PhantomData<&'a ()>,
 }
 
 impl<'a> DirectComposer<'a> {
     pub fn new() -> Self {
         Self {
             config: Self::config(),
-            values: BTreeMap::new(),
             ..Default::default()
         }
     }
+
+    /// Sets how values are escaped when rendered into the SQL, `LiteralStyle::Standard` by default.
+    pub fn set_literal_style(&mut self, style: LiteralStyle) {
+        self.literal_style = style;
+    }
 }
 
 impl<'a> Composer for DirectComposer<'a> {
-    type Value = &'a str;
+    type Value = &'a dyn ToValue;
 
     fn config() -> ComposerConfig {
         ComposerConfig { start: 0 }
     }
 
-    //TODO: error handling
-    fn binding_tag(&self, _u: usize, name: String) -> String {
-        let mut s = String::new();
-
-        if let Some(values) = self.values.get(&name) {
-            for value in values {
-                if s.len() > 0 {
-                    s.push(',');
-                }
-
-                s.push_str(&value.to_sql_text().unwrap().to_string());
-            }
-        }
-        else {
-            panic!("don't have proper error handling yet!");
-        }
-
-        s
-    }
-
-    fn compose_binding(
-        &self,
-        binding: SqlBinding,
-        offset: usize,
-    ) -> Result<(String, Vec<Self::Value>), ()> {
-        Ok((self.binding_tag(offset, binding.name), vec![]))
-    }
-
-    fn get_values(&self, _name: String) -> Option<&Vec<Self::Value>> {
-        None
+    /// Values are written into the sql by `compose_value()`, so this is only the placeholder used
+    /// where a value can't be.
+    fn binding_tag(&self, _u: usize, name: String, _tags: &mut BindingTags) -> String {
+        format!(":{}", name)
     }
 
-    fn compose_count_command(
+    fn compose_value(
         &self,
-        composition: &ParsedItem<SqlComposition>,
-        offset: usize,
-        child: bool,
+        _u: usize,
+        _name: String,
+        value: &Self::Value,
+        _tags: &mut BindingTags,
     ) -> Result<(String, Vec<Self::Value>), ()> {
-        self.compose_count_default_command(composition, offset, child)
+        Ok((value.to_sql_text(self.literal_style)?, vec![]))
     }
 
-    fn compose_union_command(
-        &self,
-        composition: &ParsedItem<SqlComposition>,
-        offset: usize,
-        child: bool,
-    ) -> Result<(String, Vec<Self::Value>), ()> {
-        self.compose_union_default_command(composition, offset, child)
+    fn literal_style(&self) -> LiteralStyle {
+        self.literal_style
     }
 
-    fn insert_value(&mut self, _name: String, _values: Vec<Self::Value>) -> () {
-        //self.values.insert(name, values);
+    fn value_text(&self, value: &Self::Value) -> Result<String, ()> {
+        match value.to_value()? {
+            Value::Text(t) => Ok(t),
+            _ => Err(()),
+        }
     }
 
-    fn root_mock_values(&self) -> &Vec<BTreeMap<String, Self::Value>> {
-        &self.root_mock_values
+    fn get_macro(&self, name: String) -> Option<&dyn MacroHandler> {
+        self.macros.get(&name).map(|m| m.as_ref())
     }
 
-    fn mock_values(&self) -> &HashMap<SqlCompositionAlias, Vec<BTreeMap<String, Self::Value>>> {
-        &self.mock_values
+    fn insert_macro(&mut self, name: String, handler: Box<dyn MacroHandler>) {
+        self.macros.insert(name.to_lowercase(), handler);
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::bind_values;
+    use crate::{bind_values, mock_path_values, mock_values};
 
-    use super::{Composer, DirectComposer, ToValue};
+    use super::{Composer, DirectComposer, LiteralStyle, ToValue, Value};
+    use crate::composer::ComposerContext;
     use crate::parser::parse_template;
 
-    use crate::types::Span;
+    use crate::types::{Span, SqlComposition, SqlCompositionAlias};
+
+    use std::collections::HashMap;
 
     use chrono::prelude::*;
 
+    use rust_decimal::Decimal;
+
+    use serde_json::json;
+
+    use uuid::Uuid;
+
     #[derive(Debug, PartialEq)]
     struct Person {
         id:           i32,
@@ -131,19 +122,19 @@ mod tests {
 
         assert_eq!(remaining.fragment, "", "nothing remaining");
 
-        let mut composer = DirectComposer::new();
+        let composer = DirectComposer::new();
 
-        composer.values = bind_values!(&dyn ToValue:
+        let bind_values = bind_values!(&dyn ToValue:
         "name" => [&person.name],
         "time_created" => [&person.time_created],
         "data" => [&person.data]
         );
 
         let (bound_sql, _bindings) = composer
-            .compose(&insert_stmt.item)
+            .compose(&insert_stmt.item, &bind_values, &HashMap::new())
             .expect("compose should work");
 
-        let now_value = now.with_timezone(&Utc).format("%Y-%m-%dT%H:%M:%S%.f");
+        let now_value = now.format("%Y-%m-%d %H:%M:%S%.f%:z");
 
         let expected_bound_sql = format!(
             "INSERT INTO person (name, time_created, data) VALUES ( '{}', '{}', {} );",
@@ -157,7 +148,7 @@ mod tests {
         assert_eq!(remaining.fragment, "", "nothing remaining");
 
         let (bound_sql, _bindings) = composer
-            .compose(&select_stmt.item)
+            .compose(&select_stmt.item, &bind_values, &HashMap::new())
             .expect("compose should work");
 
         let expected_bound_sql = format!("SELECT id, name, time_created, data FROM person WHERE name = '{}' AND time_created = '{}' AND name = '{}' AND time_created = '{}';", &person.name, now_value, &person.name, now_value);
@@ -166,5 +157,208 @@ mod tests {
     }
 
     #[test]
-    fn test_union_command() {}
+    fn test_literal_escaping() {
+        let (_remaining, stmt) = parse_template(Span::new("INSERT INTO person (name, data, score) VALUES (:bind(name), :bind(data), :bind(score));".into()), None).unwrap();
+
+        let name = "O'Brien\\'; DROP TABLE person; --".to_string();
+        let data: Vec<u8> = vec![0, 159, 146, 150];
+        let score = 2.0f64;
+
+        let mut composer = DirectComposer::new();
+
+        let bind_values = bind_values!(&dyn ToValue:
+        "name" => [&name],
+        "data" => [&data],
+        "score" => [&score]
+        );
+
+        let expected = vec![
+            (LiteralStyle::Standard, "INSERT INTO person (name, data, score) VALUES ( 'O''Brien\\''; DROP TABLE person; --', X'009f9296', 2.0 );"),
+            (LiteralStyle::MySql, "INSERT INTO person (name, data, score) VALUES ( 'O''Brien\\\\''; DROP TABLE person; --', X'009f9296', 2.0 );"),
+            (LiteralStyle::Postgres, "INSERT INTO person (name, data, score) VALUES ( E'O''Brien\\\\''; DROP TABLE person; --', E'\\\\x009f9296'::bytea, 2.0 );"),
+        ];
+
+        for (style, expected_bound_sql) in expected {
+            composer.set_literal_style(style);
+
+            let (bound_sql, _bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+            assert_eq!(bound_sql, expected_bound_sql, "{:?} literals", style);
+        }
+
+        assert_eq!(
+            Value::Text("O'Brien".into()).to_sql_text(LiteralStyle::Postgres),
+            Ok("'O''Brien'".to_string()),
+            "text without a backslash isn't an escape string"
+        );
+
+        assert_eq!(
+            Value::Text("C:\\temp".into()).to_sql_text(LiteralStyle::Postgres),
+            Ok("E'C:\\\\temp'".to_string()),
+            "backslashes doubled in an escape string"
+        );
+
+        let nan = f64::NAN;
+
+        let bind_values = bind_values!(&dyn ToValue:
+        "name" => [&name],
+        "data" => [&data],
+        "score" => [&nan]
+        );
+
+        assert!(composer.compose(&stmt.item, &bind_values, &HashMap::new()).is_err(), "NaN has no literal");
+
+        let bind_values = bind_values!(&dyn ToValue:
+        "name" => [&name],
+        "data" => [&data]
+        );
+
+        assert!(composer.compose(&stmt.item, &bind_values, &HashMap::new()).is_err(), "missing binding");
+    }
+
+    #[test]
+    fn test_typed_literals() {
+        let (_remaining, stmt) = parse_template(Span::new("INSERT INTO event (active, day, at, logged, amount, id, doc) VALUES (:bind(active), :bind(day), :bind(at), :bind(logged), :bind(amount), :bind(id), :bind(doc));".into()), None).unwrap();
+
+        let active = true;
+        let day = NaiveDate::from_ymd_opt(2019, 7, 4).unwrap();
+        let at = NaiveTime::from_hms_milli_opt(12, 30, 5, 250).unwrap();
+        let logged = FixedOffset::east_opt(2 * 3600)
+            .unwrap()
+            .from_local_datetime(&day.and_hms_opt(12, 30, 5).unwrap())
+            .single()
+            .unwrap();
+        let amount = Decimal::new(-123456, 3);
+        let id = Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+        let doc = json!({"name": "O'Brien"});
+
+        let mut composer = DirectComposer::new();
+
+        let bind_values = bind_values!(&dyn ToValue:
+        "active" => [&active],
+        "day" => [&day],
+        "at" => [&at],
+        "logged" => [&logged],
+        "amount" => [&amount],
+        "id" => [&id],
+        "doc" => [&doc]
+        );
+
+        let expected = vec![
+            (LiteralStyle::Standard, "INSERT INTO event (active, day, at, logged, amount, id, doc) VALUES ( 1, '2019-07-04', '12:30:05.250', '2019-07-04 12:30:05+02:00', -123.456, '67e55044-10b1-426f-9247-bb680e5fe0c8', '{\"name\":\"O''Brien\"}' );"),
+            (LiteralStyle::MySql, "INSERT INTO event (active, day, at, logged, amount, id, doc) VALUES ( TRUE, '2019-07-04', '12:30:05.250', '2019-07-04 10:30:05', -123.456, '67e55044-10b1-426f-9247-bb680e5fe0c8', '{\"name\":\"O''Brien\"}' );"),
+            (LiteralStyle::Postgres, "INSERT INTO event (active, day, at, logged, amount, id, doc) VALUES ( TRUE, '2019-07-04'::date, '12:30:05.250'::time, '2019-07-04 12:30:05+02:00'::timestamptz, -123.456, '67e55044-10b1-426f-9247-bb680e5fe0c8'::uuid, '{\"name\":\"O''Brien\"}'::jsonb );"),
+        ];
+
+        for (style, expected_bound_sql) in expected {
+            composer.set_literal_style(style);
+
+            let (bound_sql, _bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+            assert_eq!(bound_sql, expected_bound_sql, "{:?} literals", style);
+        }
+    }
+
+    #[test]
+    fn test_expecting() {
+        let (_remaining, stmt) = parse_template(Span::new("SELECT * FROM person AS p WHERE id IN (:bind(ids EXPECTING MIN 2 MAX 3)) AND name = :bind(name);".into()), None).unwrap();
+
+        let composer = DirectComposer::new();
+
+        let mut bind_values = bind_values!(&dyn ToValue:
+        "ids" => [&1i64, &2i64],
+        "name" => [&"Steven"]
+        );
+
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+        assert_eq!(
+            bound_sql,
+            "SELECT * FROM person AS p WHERE id IN ( 1, 2 ) AND name = 'Steven';"
+        );
+        assert_eq!(bindings.len(), 0, "values are in the sql");
+
+        bind_values.insert("ids".into(), vec![&1i64]);
+
+        assert!(composer.compose(&stmt.item, &bind_values, &HashMap::new()).is_err(), "too few values");
+
+        bind_values.insert("ids".into(), vec![&1i64, &2i64, &3i64, &4i64]);
+
+        assert!(composer.compose(&stmt.item, &bind_values, &HashMap::new()).is_err(), "too many values");
+
+        bind_values.insert("ids".into(), vec![&1i64, &2i64]);
+        bind_values.insert("name".into(), vec![&"Steven", &"Steve"]);
+
+        assert!(composer.compose(&stmt.item, &bind_values, &HashMap::new()).is_err(), "single value binding");
+    }
+
+    #[test]
+    fn test_mock_values() {
+        let stmt = SqlComposition::from_path_name("src/tests/values/include.tql".into()).unwrap();
+
+        let composer = DirectComposer::new();
+
+        let bind_values = bind_values!(&dyn ToValue:
+        "a" => [&"a_value"],
+        "b" => [&"b_value"],
+        "d" => [&"d_value"],
+        "e" => [&"e_value"]
+        );
+
+        let mock_values = mock_path_values!(&dyn ToValue: "src/tests/values/simple.tql" => [{
+            "col_1" => &1i64,
+            "col_2" => &"it's",
+            "col_3" => &2.5f64,
+            "col_4" => &"d"
+        }]);
+
+        let (bound_sql, _bindings) = composer.compose(&stmt.item, &bind_values, &mock_values).expect("compose should work");
+
+        assert_eq!(bound_sql, "SELECT 'e_value' AS col_1, 'd_value' AS col_2, 'b_value' AS col_3, 'a_value' AS col_4 UNION ALL SELECT 1 AS col_1, 'it''s' AS col_2, 2.5 AS col_3, 'd' AS col_4;");
+
+        let root_mock_values = mock_values!(&dyn ToValue: {
+            "col_1" => &"e_value"
+        },
+        {
+            "col_1" => &"a_value"
+        });
+
+        let (mock_sql, _mock_bindings) = ComposerContext::new(&composer, &bind_values, &HashMap::new())
+            .mock_compose(&root_mock_values, 0)
+            .expect("mock_compose should work");
+
+        assert_eq!(
+            mock_sql,
+            "SELECT 'e_value' AS col_1 UNION ALL SELECT 'a_value' AS col_1"
+        );
+    }
+
+    #[test]
+    fn test_union_command() {
+        let (_remaining, stmt) = parse_template(
+            Span::new(
+                ":union(src/tests/values/simple.tql, src/tests/values/simple.tql);".into(),
+            ),
+            None,
+        )
+        .unwrap();
+
+        let composer = DirectComposer::new();
+
+        let mut bind_values = bind_values!(&dyn ToValue:
+        "a" => [&"a_value"],
+        "b" => [&"b_value"],
+        "c" => [&"c_value"]
+        );
+
+        assert!(composer.compose(&stmt.item, &bind_values, &HashMap::new()).is_err(), "missing binding");
+
+        bind_values.insert("d".into(), vec![&4i64]);
+
+        let (bound_sql, _bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+        let simple = "SELECT 'a_value' AS col_1, 'b_value' AS col_2, 'c_value' AS col_3, 4 AS col_4";
+
+        assert_eq!(bound_sql, format!("{} UNION {}", simple, simple));
+    }
 }
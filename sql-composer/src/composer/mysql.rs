@@ -1,13 +1,18 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::HashMap;
+use std::marker::PhantomData;
 
 use mysql::{prelude::ToValue, Stmt};
 
 #[cfg(feature = "composer-serde")]
-use mysql::Value;
+use mysql::consts::ColumnType;
 
-use super::{Composer, ComposerConfig, ComposerConnection};
+#[cfg(feature = "composer-serde")]
+use mysql::{Column, Value};
+
+use super::{BindValue, BindingTags, Composer, ComposerConfig, ComposerConnection, IdentifierQuoting,
+            MacroHandler};
 
-use crate::types::{ParsedItem, SqlComposition, SqlCompositionAlias};
+use crate::types::value::{LiteralStyle, Value as ComposerValue};
 
 #[cfg(feature = "composer-serde")]
 use crate::types::SerdeValue;
@@ -17,6 +22,8 @@ use serde_value::Value as SerdeValueEnum;
 
 use mysql::Pool;
 
+use chrono::{Datelike, NaiveDateTime, Timelike, Utc};
+
 #[cfg(feature = "composer-serde")]
 impl Into<Value> for SerdeValue {
     fn into(self) -> Value {
@@ -28,49 +35,189 @@ impl Into<Value> for SerdeValue {
         }
     }
 }
+
+fn mysql_datetime(ts: &NaiveDateTime) -> mysql::Value {
+    mysql::Value::Date(
+        ts.year() as u16,
+        ts.month() as u8,
+        ts.day() as u8,
+        ts.hour() as u8,
+        ts.minute() as u8,
+        ts.second() as u8,
+        ts.nanosecond() / 1000,
+    )
+}
+
+/// Types MySQL doesn't have are bound as text, and timestamps with an offset in UTC.
+impl From<ComposerValue> for mysql::Value {
+    fn from(value: ComposerValue) -> Self {
+        match value {
+            ComposerValue::Null => mysql::Value::NULL,
+            ComposerValue::Bool(b) => mysql::Value::Int(b as i64),
+            ComposerValue::Integer(i) => mysql::Value::Int(i),
+            ComposerValue::Real(f) => mysql::Value::Float(f),
+            ComposerValue::Decimal(d) => mysql::Value::Bytes(d.to_string().into_bytes()),
+            ComposerValue::Text(t) => mysql::Value::Bytes(t.into_bytes()),
+            ComposerValue::Blob(b) => mysql::Value::Bytes(b),
+            ComposerValue::Date(d) => mysql::Value::Date(
+                d.year() as u16,
+                d.month() as u8,
+                d.day() as u8,
+                0,
+                0,
+                0,
+                0,
+            ),
+            ComposerValue::Time(t) => mysql::Value::Time(
+                false,
+                0,
+                t.hour() as u8,
+                t.minute() as u8,
+                t.second() as u8,
+                t.nanosecond() / 1000,
+            ),
+            ComposerValue::Timestamp(ts) => mysql_datetime(&ts),
+            ComposerValue::TimestampTz(ts) => mysql_datetime(&ts.with_timezone(&Utc).naive_utc()),
+            ComposerValue::Uuid(u) => mysql::Value::Bytes(u.to_hyphenated().to_string().into_bytes()),
+            ComposerValue::Json(j) => mysql::Value::Bytes(j.to_string().into_bytes()),
+        }
+    }
+}
+
 impl<'a> ComposerConnection<'a> for Pool {
     type Composer = MysqlComposer<'a>;
     type Value = &'a (dyn ToValue + 'a);
     type Statement = Stmt<'a>;
 
-    fn compose(
-        &'a self,
-        s: &SqlComposition,
-        values: BTreeMap<String, Vec<Self::Value>>,
-        root_mock_values: Vec<BTreeMap<String, Self::Value>>,
-        mock_values: HashMap<SqlCompositionAlias, Vec<BTreeMap<String, Self::Value>>>,
-    ) -> Result<(Self::Statement, Vec<Self::Value>), ()> {
-        let c = MysqlComposer {
-            config: MysqlComposer::config(),
-            values,
-            root_mock_values,
-            mock_values,
-        };
+    fn composer(&self) -> Self::Composer {
+        MysqlComposer::new()
+    }
 
-        let (sql, bind_vars) = c.compose(s)?;
+    fn prepare_sql(&'a self, sql: &str) -> Result<Self::Statement, ()> {
+        //TODO: support a DriverError type to handle this better
+        self.prepare(sql).map_err(|_| ())
+    }
 
+    #[cfg(feature = "composer-serde")]
+    fn query_values(
+        &self,
+        stmt: &mut Self::Statement,
+        values: &[Self::Value],
+    ) -> Result<Vec<SerdeValueEnum>, ()> {
         //TODO: support a DriverError type to handle this better
-        let stmt = self.prepare(&sql).or_else(|_| Err(()))?;
+        let rows = stmt.execute(values).map_err(|_| ())?;
 
-        Ok((stmt, bind_vars))
+        rows.map(|row| row.map(|row| row_value(&row)).map_err(|_| ()))
+            .collect()
+    }
+}
+
+/// Maps a row to a map of its column names and values, which can be deserialized into any type.
+/// Bytes are read as text unless the column holds binary strings, and dates and times as text in
+/// the formats the other drivers bind them in.
+#[cfg(feature = "composer-serde")]
+pub fn row_value(row: &mysql::Row) -> SerdeValueEnum {
+    let columns = row
+        .columns_ref()
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let v = match row.as_ref(i) {
+                None | Some(Value::NULL) => SerdeValueEnum::Unit,
+                Some(Value::Bytes(b)) if is_binary(column) => SerdeValueEnum::Bytes(b.to_vec()),
+                Some(Value::Bytes(b)) => {
+                    SerdeValueEnum::String(String::from_utf8_lossy(b).into_owned())
+                }
+                Some(Value::Int(i)) => SerdeValueEnum::I64(*i),
+                Some(Value::UInt(u)) => SerdeValueEnum::U64(*u),
+                Some(Value::Float(f)) => SerdeValueEnum::F64(*f),
+                Some(Value::Date(year, month, day, hour, minutes, seconds, micro_seconds)) => {
+                    let date = format!("{:04}-{:02}-{:02}", year, month, day);
+
+                    match column.column_type() {
+                        ColumnType::MYSQL_TYPE_DATE | ColumnType::MYSQL_TYPE_NEWDATE => {
+                            SerdeValueEnum::String(date)
+                        }
+                        _ => SerdeValueEnum::String(format!(
+                            "{} {:02}:{:02}:{:02}{}",
+                            date,
+                            hour,
+                            minutes,
+                            seconds,
+                            fraction(*micro_seconds)
+                        )),
+                    }
+                }
+                Some(Value::Time(is_negative, days, hours, minutes, seconds, micro_seconds)) => {
+                    SerdeValueEnum::String(format!(
+                        "{}{:02}:{:02}:{:02}{}",
+                        if *is_negative { "-" } else { "" },
+                        days * 24 + *hours as u32,
+                        minutes,
+                        seconds,
+                        fraction(*micro_seconds)
+                    ))
+                }
+            };
+
+            (SerdeValueEnum::String(column.name_str().to_string()), v)
+        })
+        .collect();
+
+    SerdeValueEnum::Map(columns)
+}
+
+/// Whether a column holds binary strings, BINARY, VARBINARY or a BLOB, rather than text. Numbers
+/// sent as bytes also have the binary character set, but not a string column type.
+#[cfg(feature = "composer-serde")]
+fn is_binary(column: &Column) -> bool {
+    const BINARY_CHARSET: u16 = 63;
+
+    match column.column_type() {
+        ColumnType::MYSQL_TYPE_VARCHAR
+        | ColumnType::MYSQL_TYPE_VAR_STRING
+        | ColumnType::MYSQL_TYPE_STRING
+        | ColumnType::MYSQL_TYPE_TINY_BLOB
+        | ColumnType::MYSQL_TYPE_MEDIUM_BLOB
+        | ColumnType::MYSQL_TYPE_LONG_BLOB
+        | ColumnType::MYSQL_TYPE_BLOB => column.character_set() == BINARY_CHARSET,
+        _ => false,
+    }
+}
+
+/// Fractional seconds written as chrono's `%.f` writes them: none, milliseconds or microseconds.
+#[cfg(feature = "composer-serde")]
+fn fraction(micro_seconds: u32) -> String {
+    if micro_seconds == 0 {
+        String::new()
+    }
+    else if micro_seconds % 1000 == 0 {
+        format!(".{:03}", micro_seconds / 1000)
+    }
+    else {
+        format!(".{:06}", micro_seconds)
+    }
+}
+
+impl<'a, T: ToValue> BindValue<'a, &'a (dyn ToValue + 'a)> for T {
+    fn bind_value(&'a self) -> &'a (dyn ToValue + 'a) {
+        self
     }
 }
 
 pub struct MysqlComposer<'a> {
     #[allow(dead_code)]
-    config:           ComposerConfig,
-    values:           BTreeMap<String, Vec<&'a dyn ToValue>>,
-    root_mock_values: Vec<BTreeMap<String, &'a dyn ToValue>>,
-    mock_values:      HashMap<SqlCompositionAlias, Vec<BTreeMap<String, &'a dyn ToValue>>>,
+    config: ComposerConfig,
+    macros: HashMap<String, Box<dyn MacroHandler>>,
+    values: PhantomData<&'a ()>,
 }
 
 impl<'a> MysqlComposer<'a> {
     pub fn new() -> Self {
         Self {
-            config:           Self::config(),
-            values:           BTreeMap::new(),
-            root_mock_values: vec![],
-            mock_values:      HashMap::new(),
+            config: Self::config(),
+            macros: HashMap::new(),
+            values: PhantomData,
         }
     }
 }
@@ -82,42 +229,36 @@ impl<'a> Composer for MysqlComposer<'a> {
         ComposerConfig { start: 0 }
     }
 
-    fn binding_tag(&self, _u: usize, _name: String) -> String {
+    fn binding_tag(&self, _u: usize, _name: String, _tags: &mut BindingTags) -> String {
         format!("?")
     }
 
-    fn compose_count_command(
-        &self,
-        composition: &ParsedItem<SqlComposition>,
-        offset: usize,
-        child: bool,
-    ) -> Result<(String, Vec<Self::Value>), ()> {
-        self.compose_count_default_command(composition, offset, child)
+    fn value_text(&self, value: &Self::Value) -> Result<String, ()> {
+        match value.to_value() {
+            mysql::Value::Bytes(b) => String::from_utf8(b).or(Err(())),
+            _ => Err(()),
+        }
     }
 
-    fn compose_union_command(
-        &self,
-        composition: &ParsedItem<SqlComposition>,
-        offset: usize,
-        child: bool,
-    ) -> Result<(String, Vec<Self::Value>), ()> {
-        self.compose_union_default_command(composition, offset, child)
+    fn identifier_quoting(&self) -> IdentifierQuoting {
+        IdentifierQuoting::Backtick
     }
 
-    fn get_values(&self, name: String) -> Option<&Vec<Self::Value>> {
-        self.values.get(&name)
+    fn literal_style(&self) -> LiteralStyle {
+        LiteralStyle::MySql
     }
 
-    fn insert_value(&mut self, name: String, values: Vec<Self::Value>) -> () {
-        self.values.insert(name, values);
+    /// The largest unsigned BIGINT, as the MySQL manual recommends for an OFFSET without a LIMIT.
+    fn unbounded_limit(&self) -> Option<&str> {
+        Some("18446744073709551615")
     }
 
-    fn root_mock_values(&self) -> &Vec<BTreeMap<String, Self::Value>> {
-        &self.root_mock_values
+    fn get_macro(&self, name: String) -> Option<&dyn MacroHandler> {
+        self.macros.get(&name).map(|m| m.as_ref())
     }
 
-    fn mock_values(&self) -> &HashMap<SqlCompositionAlias, Vec<BTreeMap<String, Self::Value>>> {
-        &self.mock_values
+    fn insert_macro(&mut self, name: String, handler: Box<dyn MacroHandler>) {
+        self.macros.insert(name.to_lowercase(), handler);
     }
 }
 
@@ -126,6 +267,7 @@ mod tests {
     use crate::{bind_values, mock_db_object_values, mock_path_values, mock_values};
 
     use super::{Composer, ComposerConnection, MysqlComposer};
+    use crate::composer::ComposerContext;
 
     use crate::parser::parse_template;
     use crate::types::{ParsedItem, Span, SqlComposition, SqlCompositionAlias, SqlDbObject};
@@ -178,7 +320,7 @@ mod tests {
             data: None,
         };
 
-        let mut composer = MysqlComposer::new();
+        let composer = MysqlComposer::new();
 
         let (remaining, insert_stmt) = parse_template(
             Span::new("INSERT INTO person (name, data) VALUES (:bind(name), :bind(data));".into()),
@@ -188,13 +330,13 @@ mod tests {
 
         assert_eq!(remaining.fragment, "", "insert stmt nothing remaining");
 
-        composer.values = bind_values!(&dyn ToValue:
+        let bind_values = bind_values!(&dyn ToValue:
         "name" => [&person.name],
         "data" => [&person.data]
         );
 
         let (bound_sql, bindings) = composer
-            .compose(&insert_stmt.item)
+            .compose(&insert_stmt.item, &bind_values, &HashMap::new())
             .expect("compose should work");
 
         let expected_bound_sql = "INSERT INTO person (name, data) VALUES ( ?, ? );";
@@ -208,7 +350,7 @@ mod tests {
         assert_eq!(remaining.fragment, "", "select stmt nothing remaining");
 
         let (bound_sql, bindings) = composer
-            .compose(&select_stmt.item)
+            .compose(&select_stmt.item, &bind_values, &HashMap::new())
             .expect("compose should work");
 
         let expected_bound_sql = "SELECT id, name, data FROM person WHERE name = ? AND name = ?;";
@@ -261,20 +403,18 @@ mod tests {
 
         let stmt = SqlComposition::from_path_name("src/tests/values/simple.tql".into()).unwrap();
 
-        let mut composer = MysqlComposer::new();
+        let composer = MysqlComposer::new();
 
-        composer.values = bind_values!(&dyn ToValue:
+        let bind_values = bind_values!(&dyn ToValue:
                                        "a" => [&"a_value"],
                                        "b" => [&"b_value"],
                                        "c" => [&"c_value"],
                                        "d" => [&"d_value"]
         );
 
-        let (bound_sql, bindings) = composer.compose(&stmt.item).expect("compose should work");
-        composer.root_mock_values = mock_values!(&dyn ToValue: {"col_1" => &"a_value", "col_2" => &"b_value", "col_3" => &"c_value", "col_4" => &"d_value"});
-
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
         let (mock_bound_sql, mock_bindings) =
-            composer.compose(&stmt.item).expect("compose should work");
+            composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
 
         let mut prep_stmt = pool.prepare(&bound_sql).unwrap();
 
@@ -301,9 +441,9 @@ mod tests {
 
         let stmt = SqlComposition::from_path_name("src/tests/values/include.tql".into()).unwrap();
 
-        let mut composer = MysqlComposer::new();
+        let composer = MysqlComposer::new();
 
-        composer.values = bind_values!(&dyn ToValue:
+        let bind_values = bind_values!(&dyn ToValue:
                                        "a" => [&"a_value"],
                                        "b" => [&"b_value"],
                                        "c" => [&"c_value"],
@@ -323,8 +463,10 @@ mod tests {
             "col_4" => &"d_value"
         });
 
-        let (bound_sql, bindings) = composer.compose(&stmt.item).expect("compose should work");
-        let (mut mock_bound_sql, mock_bindings) = composer.mock_compose(&mock_values, 0);
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+        let (mut mock_bound_sql, mock_bindings) = ComposerContext::new(&composer, &bind_values, &HashMap::new())
+            .mock_compose(&mock_values, 0)
+            .expect("mock_compose should work");
 
         mock_bound_sql.push(';');
 
@@ -355,9 +497,9 @@ mod tests {
         let stmt =
             SqlComposition::from_path_name("src/tests/values/double-include.tql".into()).unwrap();
 
-        let mut composer = MysqlComposer::new();
+        let composer = MysqlComposer::new();
 
-        composer.values = bind_values!(&dyn ToValue:
+        let bind_values = bind_values!(&dyn ToValue:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
@@ -385,8 +527,10 @@ mod tests {
         "col_4" => &"d_value"
         });
 
-        let (bound_sql, bindings) = composer.compose(&stmt.item).expect("compose should work");
-        let (mut mock_bound_sql, mock_bindings) = composer.mock_compose(&mock_values, 1);
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+        let (mut mock_bound_sql, mock_bindings) = ComposerContext::new(&composer, &bind_values, &HashMap::new())
+            .mock_compose(&mock_values, 1)
+            .expect("mock_compose should work");
 
         mock_bound_sql.push(';');
 
@@ -433,9 +577,9 @@ mod tests {
             vec!["a_value", "b_value", "c_value", "d_value"],
         ];
 
-        let mut composer = MysqlComposer::new();
+        let composer = MysqlComposer::new();
 
-        composer.values = bind_values!(&dyn ToValue:
+        let bind_values = bind_values!(&dyn ToValue:
                                        "a" => [&"a_value"],
                                        "b" => [&"b_value"],
                                        "c" => [&"c_value"],
@@ -448,7 +592,7 @@ mod tests {
                                        &"c_value"]
         );
 
-        let (bound_sql, bindings) = composer.compose(&stmt.item).expect("compose should work");
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
 
         assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
 
@@ -475,9 +619,9 @@ mod tests {
 
         let expected_bound_sql = "SELECT COUNT(1) FROM ( SELECT ? AS col_1, ? AS col_2, ? AS col_3, ? AS col_4 UNION ALL SELECT ? AS col_1, ? AS col_2, ? AS col_3, ? AS col_4 UNION ALL SELECT ? AS col_1, ? AS col_2, ? AS col_3, ? AS col_4 ) AS count_main";
 
-        let mut composer = MysqlComposer::new();
+        let composer = MysqlComposer::new();
 
-        composer.values = bind_values!(&dyn ToValue:
+        let bind_values = bind_values!(&dyn ToValue:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
@@ -488,7 +632,7 @@ mod tests {
         "col_3_values" => [&"b_value", &"c_value"]
         );
 
-        let (bound_sql, bindings) = composer.compose(&stmt.item).expect("compose should work");
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
 
         assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
 
@@ -514,9 +658,9 @@ mod tests {
 
         let expected_bound_sql = "SELECT ? AS col_1, ? AS col_2, ? AS col_3, ? AS col_4 UNION ALL SELECT ? AS col_1, ? AS col_2, ? AS col_3, ? AS col_4 UNION ALL SELECT ? AS col_1, ? AS col_2, ? AS col_3, ? AS col_4 UNION SELECT ? AS col_1, ? AS col_2, ? AS col_3, ? AS col_4 UNION ALL SELECT ? AS col_1, ? AS col_2, ? AS col_3, ? AS col_4 UNION SELECT ? AS col_1, ? AS col_2, ? AS col_3, ? AS col_4 UNION ALL SELECT ? AS col_1, ? AS col_2, ? AS col_3, ? AS col_4 UNION ALL SELECT ? AS col_1, ? AS col_2, ? AS col_3, ? AS col_4";
 
-        let mut composer = MysqlComposer::new();
+        let composer = MysqlComposer::new();
 
-        composer.values = bind_values!(&dyn ToValue:
+        let bind_values = bind_values!(&dyn ToValue:
                                        "a" => [&"a_value"],
                                        "b" => [&"b_value"],
                                        "c" => [&"c_value"],
@@ -527,7 +671,7 @@ mod tests {
                                        "col_3_values" => [&"b_value", &"c_value"]
         );
 
-        let (bound_sql, bindings) = composer.compose(&stmt.item).expect("compose should work");
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
 
         assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
 
@@ -563,9 +707,9 @@ mod tests {
             vec!["ee_value", "dd_value", "bb_value", "aa_value"],
         ];
 
-        let mut composer = MysqlComposer::new();
+        let composer = MysqlComposer::new();
 
-        composer.values = bind_values!(&dyn ToValue:
+        let bind_values = bind_values!(&dyn ToValue:
                                        "a" => [&"a_value"],
                                        "b" => [&"b_value"],
                                        "c" => [&"c_value"],
@@ -576,7 +720,7 @@ mod tests {
                                        "col_3_values" => [&"bb_value", &"b_value"]
         );
 
-        composer.mock_values = mock_path_values!(&dyn ToValue: "src/tests/values/include.tql" => [
+        let mock_values = mock_path_values!(&dyn ToValue: "src/tests/values/include.tql" => [
         {
         "col_1" => &"ee_value",
         "col_2" => &"dd_value",
@@ -584,7 +728,7 @@ mod tests {
         "col_4" => &"aa_value"
         }]);
 
-        let (bound_sql, bindings) = composer
+        let (bound_sql, bindings) = ComposerContext::new(&composer, &bind_values, &mock_values)
             .compose_statement(&stmt, 0, false)
             .expect("compose_statement should work");
 
@@ -615,9 +759,9 @@ mod tests {
             vec!["aa_value", "bb_value", "cc_value", "dd_value"],
         ];
 
-        let mut composer = MysqlComposer::new();
+        let composer = MysqlComposer::new();
 
-        composer.values = bind_values!(&dyn ToValue:
+        let bind_values = bind_values!(&dyn ToValue:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
@@ -628,7 +772,7 @@ mod tests {
         "col_3_values" => [&"bb_value", &"cc_value"]
         );
 
-        composer.mock_values = mock_path_values!(&dyn ToValue: "src/tests/values/double-include.tql" => [
+        let mock_values = mock_path_values!(&dyn ToValue: "src/tests/values/double-include.tql" => [
         {
             "col_1" => &"dd_value",
             "col_2" => &"ff_value",
@@ -648,7 +792,7 @@ mod tests {
             "col_4" => &"dd_value"
         }]);
 
-        let (bound_sql, bindings) = composer
+        let (bound_sql, bindings) = ComposerContext::new(&composer, &bind_values, &mock_values)
             .compose_statement(&stmt, 0, false)
             .expect("compose_statement should work");
 
@@ -679,9 +823,9 @@ mod tests {
             vec!["aa_value", "bb_value", "cc_value", "dd_value"],
         ];
 
-        let mut composer = MysqlComposer::new();
+        let composer = MysqlComposer::new();
 
-        composer.values = bind_values!(&dyn ToValue:
+        let bind_values = bind_values!(&dyn ToValue:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
@@ -692,7 +836,7 @@ mod tests {
         "col_3_values" => [&"bb_value", &"cc_value"]
         );
 
-        composer.mock_values = mock_db_object_values!(&dyn ToValue: "main" => [{
+        let mock_values = mock_db_object_values!(&dyn ToValue: "main" => [{
             "col_1" => &"dd_value",
             "col_2" => &"ff_value",
             "col_3" => &"bb_value",
@@ -711,7 +855,7 @@ mod tests {
             "col_4" => &"dd_value"
         }]);
 
-        let (bound_sql, bindings) = composer
+        let (bound_sql, bindings) = ComposerContext::new(&composer, &bind_values, &mock_values)
             .compose_statement(&stmt, 0, false)
             .expect("compose_statement should work");
 
@@ -734,15 +878,13 @@ mod tests {
 
         let stmt = SqlComposition::from_path_name("src/tests/values/simple.tql".into()).unwrap();
 
-        let bind_values = bind_values!(&dyn ToValue:
-        "a" => [&"a_value"],
-        "b" => [&"b_value"],
-        "c" => [&"c_value"],
-        "d" => [&"d_value"]
-        );
-
         let (mut prep_stmt, bindings) = conn
-            .compose(&stmt.item, bind_values, vec![], HashMap::new())
+            .composition(&stmt.item)
+            .bind("a", &"a_value")
+            .bind("b", &"b_value")
+            .bind("c", &"c_value")
+            .bind("d", &"d_value")
+            .prepare()
             .unwrap();
 
         let mut values: Vec<Vec<String>> = vec![];
@@ -760,4 +902,82 @@ mod tests {
 
         assert_eq!(values, expected, "exected values");
     }
+
+    #[cfg(feature = "composer-serde")]
+    #[test]
+    fn test_row_value_types() {
+        use super::row_value;
+
+        use crate::types::value::Value;
+
+        use chrono::NaiveDate;
+        use rust_decimal::Decimal;
+        use serde_value::Value as SerdeValueEnum;
+        use std::collections::BTreeMap;
+
+        let pool = setup_db();
+
+        pool.prep_exec("DROP TABLE IF EXISTS typed;", ()).unwrap();
+
+        pool.prep_exec(
+            "CREATE TABLE IF NOT EXISTS typed (
+                        name            VARCHAR(20) NOT NULL,
+                        data            VARBINARY(20) NOT NULL,
+                        amount          DECIMAL(10, 3) NOT NULL,
+                        day             DATE NOT NULL,
+                        at              TIME(6) NOT NULL,
+                        ts              DATETIME(6) NOT NULL
+                      )",
+            (),
+        )
+        .unwrap();
+
+        let day = NaiveDate::from_ymd_opt(2019, 7, 4).unwrap();
+        let ts = day.and_hms_micro_opt(12, 30, 15, 250_000).unwrap();
+
+        let values: Vec<mysql::Value> = vec![
+            Value::Text("O'Brien".into()),
+            Value::Blob(b"a_value".to_vec()),
+            Value::Decimal(Decimal::new(-123456, 3)),
+            Value::Date(day),
+            Value::Time(ts.time()),
+            Value::Timestamp(ts),
+        ]
+        .into_iter()
+        .map(|v| v.into())
+        .collect();
+
+        pool.prep_exec(
+            "INSERT INTO typed (name, data, amount, day, at, ts) VALUES (?, ?, ?, ?, ?, ?);",
+            values,
+        )
+        .unwrap();
+
+        let row = pool
+            .prep_exec("SELECT * FROM typed;", ())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+
+        let text = |t: &str| SerdeValueEnum::String(t.to_string());
+
+        let expected: BTreeMap<SerdeValueEnum, SerdeValueEnum> = vec![
+            ("name", text("O'Brien")),
+            ("data", SerdeValueEnum::Bytes(b"a_value".to_vec())),
+            ("amount", text("-123.456")),
+            ("day", text("2019-07-04")),
+            ("at", text("12:30:15.250")),
+            ("ts", text("2019-07-04 12:30:15.250")),
+        ]
+        .into_iter()
+        .map(|(name, v)| (text(name), v))
+        .collect();
+
+        assert_eq!(
+            row_value(&row),
+            SerdeValueEnum::Map(expected),
+            "strings or bytes by column type, dates and times as text"
+        );
+    }
 }
@@ -1,51 +1,443 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::marker::PhantomData;
+
+use chrono::{NaiveDate, NaiveDateTime, Timelike};
 
 use postgres::stmt::Statement;
-use postgres::types::ToSql;
-#[cfg(feature = "composer-serde")]
-use postgres::types::{IsNull, Type};
+use postgres::types::{IsNull, ToSql, Type, BOOL, BOOL_ARRAY, BYTEA, BYTEA_ARRAY, DATE, DATE_ARRAY,
+                      FLOAT4, FLOAT4_ARRAY, FLOAT8, FLOAT8_ARRAY, INT2, INT2_ARRAY, INT4,
+                      INT4_ARRAY, INT8, INT8_ARRAY, JSON, JSONB, NUMERIC, TEXT, TEXT_ARRAY, TIME,
+                      TIMESTAMP, TIMESTAMPTZ, TIMESTAMPTZ_ARRAY, TIMESTAMP_ARRAY, UUID, UUID_ARRAY,
+                      VARCHAR_ARRAY};
 use postgres::Connection;
 
-use super::{Composer, ComposerConfig, ComposerConnection};
+use rust_decimal::Decimal;
+
+use super::{BindValue, BindingTags, Composer, ComposerConfig, ComposerConnection,
+            MacroHandler};
 
-use crate::types::{ParsedItem, SqlComposition, SqlCompositionAlias};
+use crate::types::value::{LiteralStyle, Value as ComposerValue};
+use crate::types::SqlBinding;
 
 #[cfg(feature = "composer-serde")]
 use crate::types::SerdeValue;
 
 #[cfg(feature = "composer-serde")]
-use serde_value::Value;
+use std::collections::BTreeMap;
 
 #[cfg(feature = "composer-serde")]
-use std::error::Error;
+use postgres::rows::Row;
+
+#[cfg(feature = "composer-serde")]
+use postgres::types::{FromSql, CHAR, NAME, OID, VARCHAR};
+
+#[cfg(feature = "composer-serde")]
+use chrono::{Duration, NaiveTime, TimeZone, Utc};
+
+#[cfg(feature = "composer-serde")]
+use uuid::Uuid;
+
+#[cfg(feature = "composer-serde")]
+use serde_value::Value;
 
 impl<'a> ComposerConnection<'a> for Connection {
     type Composer = PostgresComposer<'a>;
     type Value = &'a (dyn ToSql + 'a);
     type Statement = Statement<'a>;
 
-    fn compose(
-        &'a self,
-        s: &SqlComposition,
-        values: BTreeMap<String, Vec<&'a dyn ToSql>>,
-        root_mock_values: Vec<BTreeMap<String, Self::Value>>,
-        mock_values: HashMap<SqlCompositionAlias, Vec<BTreeMap<String, Self::Value>>>,
-    ) -> Result<(Self::Statement, Vec<Self::Value>), ()> {
-        let c = PostgresComposer {
-            #[allow(dead_code)]
-            config: PostgresComposer::config(),
-            values,
-            root_mock_values,
-            mock_values,
-        };
+    fn composer(&self) -> Self::Composer {
+        PostgresComposer::new()
+    }
 
-        let (sql, bind_vars) = c.compose(s)?;
+    fn prepare_sql(&'a self, sql: &str) -> Result<Self::Statement, ()> {
+        //TODO: support a DriverError type to handle this better
+        self.prepare(sql).map_err(|_| ())
+    }
 
+    #[cfg(feature = "composer-serde")]
+    fn query_values(
+        &self,
+        stmt: &mut Self::Statement,
+        values: &[Self::Value],
+    ) -> Result<Vec<Value>, ()> {
         //TODO: support a DriverError type to handle this better
-        let stmt = self.prepare(&sql).or_else(|_| Err(()))?;
+        let rows = stmt.query(values).map_err(|_| ())?;
+
+        rows.iter().map(|row| row_value(&row)).collect()
+    }
+}
+
+/// Maps a row to a map of its column names and values, which can be deserialized into any type.
+/// Dates, times, NUMERIC, UUID and JSON are read as text, in the formats the other drivers bind
+/// them in. Fails for a column of a type `Value` can't be bound to.
+#[cfg(feature = "composer-serde")]
+pub fn row_value(row: &Row) -> Result<Value, ()> {
+    let mut columns = BTreeMap::new();
+
+    for (i, column) in row.columns().iter().enumerate() {
+        let v = match *column.type_() {
+            BOOL => column_value(row, i, Value::Bool)?,
+            CHAR => column_value(row, i, Value::I8)?,
+            INT2 => column_value(row, i, Value::I16)?,
+            INT4 => column_value(row, i, Value::I32)?,
+            OID => column_value(row, i, Value::U32)?,
+            INT8 => column_value(row, i, Value::I64)?,
+            VARCHAR | TEXT | NAME => column_value(row, i, Value::String)?,
+            FLOAT4 => column_value(row, i, Value::F32)?,
+            FLOAT8 => column_value(row, i, Value::F64)?,
+            BYTEA => column_value(row, i, Value::Bytes)?,
+            NUMERIC | DATE | TIME | TIMESTAMP | TIMESTAMPTZ | UUID | JSON | JSONB => {
+                match row.get_opt::<_, Option<RawValue>>(i) {
+                    Some(Ok(Some(raw))) => Value::String(pg_text(column.type_(), &raw.0)?),
+                    Some(Ok(None)) => Value::Unit,
+                    _ => return Err(()),
+                }
+            }
+            //TODO: useful error
+            _ => return Err(()),
+        };
+
+        columns.insert(Value::String(column.name().to_string()), v);
+    }
+
+    Ok(Value::Map(columns))
+}
+
+#[cfg(feature = "composer-serde")]
+fn column_value<T: FromSql>(row: &Row, i: usize, f: fn(T) -> Value) -> Result<Value, ()> {
+    match row.get_opt::<_, Option<T>>(i) {
+        Some(Ok(Some(v))) => Ok(f(v)),
+        Some(Ok(None)) => Ok(Value::Unit),
+        _ => Err(()),
+    }
+}
+
+/// The binary format of a column, for the types without a `FromSql` in the driver's default
+/// features.
+#[cfg(feature = "composer-serde")]
+struct RawValue(Vec<u8>);
+
+#[cfg(feature = "composer-serde")]
+impl FromSql for RawValue {
+    fn from_sql(_ty: &Type, raw: &[u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(RawValue(raw.to_vec()))
+    }
+
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+}
+
+/// Reads the binary format of a value written by `ToSql for ComposerValue` as text.
+#[cfg(feature = "composer-serde")]
+fn pg_text(ty: &Type, raw: &[u8]) -> Result<String, ()> {
+    match *ty {
+        NUMERIC => pg_numeric_text(raw),
+        DATE => {
+            let days = i32::from_be_bytes(raw_bytes(raw)?);
+
+            let date = pg_epoch().date() + Duration::days(days as i64);
+
+            Ok(date.format("%Y-%m-%d").to_string())
+        }
+        TIME => {
+            let micros = i64::from_be_bytes(raw_bytes(raw)?);
+
+            //TODO: useful error
+            let time = NaiveTime::from_num_seconds_from_midnight_opt(
+                u32::try_from(micros / 1_000_000).or(Err(()))?,
+                (micros % 1_000_000) as u32 * 1000,
+            )
+            .ok_or(())?;
+
+            Ok(time.format("%H:%M:%S%.f").to_string())
+        }
+        TIMESTAMP => {
+            let ts = pg_epoch() + Duration::microseconds(i64::from_be_bytes(raw_bytes(raw)?));
+
+            Ok(ts.format("%Y-%m-%d %H:%M:%S%.f").to_string())
+        }
+        TIMESTAMPTZ => {
+            let ts = pg_epoch() + Duration::microseconds(i64::from_be_bytes(raw_bytes(raw)?));
+
+            Ok(Utc
+                .from_utc_datetime(&ts)
+                .format("%Y-%m-%d %H:%M:%S%.f%:z")
+                .to_string())
+        }
+        UUID => Ok(Uuid::from_slice(raw).or(Err(()))?.to_hyphenated().to_string()),
+        JSON => String::from_utf8(raw.to_vec()).or(Err(())),
+        JSONB => match raw.split_first() {
+            // jsonb's binary format is a version byte followed by the text
+            Some((1, text)) => String::from_utf8(text.to_vec()).or(Err(())),
+            _ => Err(()),
+        },
+        //TODO: useful error
+        _ => Err(()),
+    }
+}
+
+#[cfg(feature = "composer-serde")]
+fn raw_bytes<T: Default + AsMut<[u8]>>(raw: &[u8]) -> Result<T, ()> {
+    let mut bytes = T::default();
+
+    if bytes.as_mut().len() != raw.len() {
+        //TODO: useful error
+        return Err(());
+    }
+
+    bytes.as_mut().copy_from_slice(raw);
+
+    Ok(bytes)
+}
+
+/// Reads the binary NUMERIC format written by `pg_numeric()` as a decimal string.
+#[cfg(feature = "composer-serde")]
+fn pg_numeric_text(raw: &[u8]) -> Result<String, ()> {
+    if raw.len() < 8 || raw.len() % 2 != 0 {
+        //TODO: useful error
+        return Err(());
+    }
+
+    let header = |i: usize| u16::from_be_bytes([raw[i * 2], raw[i * 2 + 1]]);
+
+    let ndigits = header(0) as usize;
+    let weight = header(1) as i16;
+    let sign = header(2);
+    let scale = header(3);
+
+    if sign == 0xC000 {
+        return Ok("NaN".to_string());
+    }
+
+    let digits: Vec<u16> = raw[8..].chunks(2).map(|d| u16::from_be_bytes([d[0], d[1]])).collect();
+
+    if digits.len() != ndigits {
+        //TODO: useful error
+        return Err(());
+    }
+
+    let digit = |i: i32| {
+        if i >= 0 && (i as usize) < digits.len() {
+            digits[i as usize]
+        }
+        else {
+            0
+        }
+    };
+
+    let mut text = String::new();
+
+    if sign == 0x4000 {
+        text.push('-');
+    }
+
+    if weight < 0 {
+        text.push('0');
+    }
+    else {
+        text.push_str(&digit(0).to_string());
+
+        for i in 1..=weight as i32 {
+            text.push_str(&format!("{:04}", digit(i)));
+        }
+    }
+
+    if scale > 0 {
+        let mut frac = String::new();
+        let mut i = weight as i32 + 1;
+
+        while frac.len() < scale as usize {
+            frac.push_str(&format!("{:04}", digit(i)));
+            i += 1;
+        }
+
+        frac.truncate(scale as usize);
+
+        text.push('.');
+        text.push_str(&frac);
+    }
+
+    Ok(text)
+}
+
+impl<'a, T: ToSql> BindValue<'a, &'a (dyn ToSql + 'a)> for T {
+    fn bind_value(&'a self) -> &'a (dyn ToSql + 'a) {
+        self
+    }
+}
+
+/// Whether a value is bound as a Postgres array, found by encoding it as each of the array types
+/// this crate's drivers can write.
+fn is_array(value: &dyn ToSql) -> bool {
+    let array_types = [
+        BOOL_ARRAY,
+        INT2_ARRAY,
+        INT4_ARRAY,
+        INT8_ARRAY,
+        FLOAT4_ARRAY,
+        FLOAT8_ARRAY,
+        TEXT_ARRAY,
+        VARCHAR_ARRAY,
+        BYTEA_ARRAY,
+        DATE_ARRAY,
+        TIMESTAMP_ARRAY,
+        TIMESTAMPTZ_ARRAY,
+        UUID_ARRAY,
+    ];
+
+    let mut buf = vec![];
+
+    array_types.iter().any(|ty| {
+        buf.clear();
+
+        match value.to_sql_checked(ty, &mut buf) {
+            Ok(IsNull::No) => true,
+            _ => false,
+        }
+    })
+}
+
+fn pg_epoch() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2000, 1, 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+}
+
+fn pg_timestamp(ts: &NaiveDateTime, w: &mut Vec<u8>) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+    let micros = ts
+        .signed_duration_since(pg_epoch())
+        .num_microseconds()
+        .ok_or("timestamp out of range")?;
+
+    w.extend_from_slice(&micros.to_be_bytes());
+
+    Ok(IsNull::No)
+}
+
+/// Writes the binary NUMERIC format, base 10000 digits weighted from the decimal point.
+fn pg_numeric(d: &Decimal, w: &mut Vec<u8>) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+    let text = d.to_string();
 
-        Ok((stmt, bind_vars))
+    let negative = text.starts_with('-');
+    let text = text.trim_start_matches('-');
+
+    let (int_part, frac_part) = match text.find('.') {
+        Some(p) => (&text[..p], &text[p + 1..]),
+        None => (text, ""),
+    };
+
+    let int_part = int_part.trim_start_matches('0');
+
+    let mut padded = "0".repeat((4 - int_part.len() % 4) % 4);
+    padded.push_str(int_part);
+
+    let int_groups = padded.len() / 4;
+
+    padded.push_str(frac_part);
+    padded.push_str(&"0".repeat((4 - frac_part.len() % 4) % 4));
+
+    let mut digits = vec![];
+
+    for group in padded.as_bytes().chunks(4) {
+        digits.push(std::str::from_utf8(group)?.parse::<i16>()?);
+    }
+
+    let mut weight = int_groups as i16 - 1;
+
+    while digits.first() == Some(&0) {
+        digits.remove(0);
+        weight -= 1;
+    }
+
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+
+    if digits.is_empty() {
+        weight = 0;
+    }
+
+    let sign: u16 = if negative && !digits.is_empty() { 0x4000 } else { 0 };
+
+    w.extend_from_slice(&(digits.len() as i16).to_be_bytes());
+    w.extend_from_slice(&weight.to_be_bytes());
+    w.extend_from_slice(&sign.to_be_bytes());
+    w.extend_from_slice(&(frac_part.len() as u16).to_be_bytes());
+
+    for digit in digits {
+        w.extend_from_slice(&digit.to_be_bytes());
+    }
+
+    Ok(IsNull::No)
+}
+
+/// Values are written in the binary format of the parameter's type, so an Integer can be bound to
+/// INT2, INT4 or INT8 as long as it fits, while binding to a type the value can't represent is an
+/// error.
+impl ToSql for ComposerValue {
+    fn to_sql(&self, ty: &Type, w: &mut Vec<u8>) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        match self {
+            ComposerValue::Null => Ok(IsNull::Yes),
+            ComposerValue::Bool(b) if *ty == BOOL => <bool as ToSql>::to_sql(b, ty, w),
+            ComposerValue::Integer(i) if *ty == INT8 => <i64 as ToSql>::to_sql(i, ty, w),
+            ComposerValue::Integer(i) if *ty == INT4 => {
+                <i32 as ToSql>::to_sql(&i32::try_from(*i)?, ty, w)
+            }
+            ComposerValue::Integer(i) if *ty == INT2 => {
+                <i16 as ToSql>::to_sql(&i16::try_from(*i)?, ty, w)
+            }
+            ComposerValue::Real(f) if *ty == FLOAT8 => <f64 as ToSql>::to_sql(f, ty, w),
+            ComposerValue::Real(f) if *ty == FLOAT4 => <f32 as ToSql>::to_sql(&(*f as f32), ty, w),
+            ComposerValue::Decimal(d) if *ty == NUMERIC => pg_numeric(d, w),
+            ComposerValue::Text(t) if <String as ToSql>::accepts(ty) => {
+                <String as ToSql>::to_sql(t, ty, w)
+            }
+            ComposerValue::Blob(b) if *ty == BYTEA => <Vec<u8> as ToSql>::to_sql(b, ty, w),
+            ComposerValue::Date(d) if *ty == DATE => {
+                let days = d.signed_duration_since(pg_epoch().date()).num_days();
+
+                w.extend_from_slice(&i32::try_from(days)?.to_be_bytes());
+
+                Ok(IsNull::No)
+            }
+            ComposerValue::Time(t) if *ty == TIME => {
+                let micros = t.num_seconds_from_midnight() as i64 * 1_000_000
+                    + (t.nanosecond() / 1000) as i64;
+
+                w.extend_from_slice(&micros.to_be_bytes());
+
+                Ok(IsNull::No)
+            }
+            ComposerValue::Timestamp(ts) if *ty == TIMESTAMP => pg_timestamp(ts, w),
+            ComposerValue::TimestampTz(ts) if *ty == TIMESTAMPTZ => pg_timestamp(&ts.naive_utc(), w),
+            ComposerValue::Uuid(u) if *ty == UUID => {
+                w.extend_from_slice(u.as_bytes());
+
+                Ok(IsNull::No)
+            }
+            ComposerValue::Json(j) if *ty == JSON || *ty == JSONB => {
+                if *ty == JSONB {
+                    // jsonb's binary format is a version byte followed by the text
+                    w.push(1);
+                }
+
+                w.extend_from_slice(j.to_string().as_bytes());
+
+                Ok(IsNull::No)
+            }
+            _ => Err(format!("can't bind {:?} to a parameter of type {:?}", self, ty).into()),
+        }
     }
+
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+
+    to_sql_checked!();
 }
 
 #[cfg(feature = "composer-serde")]
@@ -85,21 +477,30 @@ impl ToSql for SerdeValue {
 #[derive(Default)]
 pub struct PostgresComposer<'a> {
     #[allow(dead_code)]
-    config:           ComposerConfig,
-    values:           BTreeMap<String, Vec<&'a dyn ToSql>>,
-    root_mock_values: Vec<BTreeMap<String, &'a dyn ToSql>>,
-    mock_values:      HashMap<SqlCompositionAlias, Vec<BTreeMap<String, &'a dyn ToSql>>>,
+    config:         ComposerConfig,
+    macros:         HashMap<String, Box<dyn MacroHandler>>,
+    array_bindings: bool,
+    values:         PhantomData<&'a ()>,
 }
 
 impl<'a> PostgresComposer<'a> {
     pub fn new() -> Self {
         Self {
-            config:           Self::config(),
-            values:           BTreeMap::new(),
-            root_mock_values: Vec::new(),
-            mock_values:      HashMap::new(),
+            config:         Self::config(),
+            macros:         HashMap::new(),
+            array_bindings: false,
+            values:         PhantomData,
         }
     }
+
+    /// Sends an array bound to a multi-valued binding used in an `IN (...)` list as a single
+    /// parameter, composing `col IN (:bind(ids EXPECTING MIN 1))` as `col = ANY($1)`, so the
+    /// statement text doesn't change with the number of ids. The array, such as a `Vec<i32>`, must
+    /// be the only value bound to the name, and its length isn't checked against EXPECTING. Scalar
+    /// values, one or several, are composed as the IN list as written.
+    pub fn set_array_bindings(&mut self, enabled: bool) {
+        self.array_bindings = enabled;
+    }
 }
 
 impl<'a> Composer for PostgresComposer<'a> {
@@ -109,48 +510,63 @@ impl<'a> Composer for PostgresComposer<'a> {
         ComposerConfig { start: 0 }
     }
 
-    fn binding_tag(&self, u: usize, _name: String) -> String {
+    fn binding_tag(&self, u: usize, _name: String, _tags: &mut BindingTags) -> String {
         format!("${}", u)
     }
 
-    fn compose_count_command(
-        &self,
-        composition: &ParsedItem<SqlComposition>,
-        offset: usize,
-        child: bool,
-    ) -> Result<(String, Vec<Self::Value>), ()> {
-        self.compose_count_default_command(composition, offset, child)
+    /// Postgres parameters can be used more than once, so a name bound to a single value keeps the
+    /// `$n` it was first given.
+    fn reuse_binding_tag(&self, u: usize, name: &str, tags: &mut BindingTags) -> Option<String> {
+        match tags.first.get(name) {
+            Some(&first) => Some(self.binding_tag(first, name.to_string(), tags)),
+            None => {
+                tags.first.insert(name.to_string(), u);
+
+                None
+            }
+        }
     }
 
-    fn compose_union_command(
-        &self,
-        composition: &ParsedItem<SqlComposition>,
-        offset: usize,
-        child: bool,
-    ) -> Result<(String, Vec<Self::Value>), ()> {
-        self.compose_union_default_command(composition, offset, child)
+    fn array_binding(&self, binding: &SqlBinding, values: &[Self::Value]) -> bool {
+        let multiple = match binding.max_values {
+            Some(max) => max > 1,
+            None => binding.min_values.is_some(),
+        };
+
+        self.array_bindings
+            && multiple
+            && binding.columns.is_none()
+            && values.len() == 1
+            && is_array(values[0])
     }
 
-    fn get_values(&self, name: String) -> Option<&Vec<Self::Value>> {
-        self.values.get(&name)
+    fn literal_style(&self) -> LiteralStyle {
+        LiteralStyle::Postgres
     }
 
-    fn insert_value(&mut self, name: String, values: Vec<Self::Value>) -> () {
-        self.values.insert(name, values);
+
+    fn value_text(&self, value: &Self::Value) -> Result<String, ()> {
+        let mut buf = vec![];
+
+        match value.to_sql_checked(&TEXT, &mut buf) {
+            Ok(IsNull::No) => String::from_utf8(buf).or(Err(())),
+            _ => Err(()),
+        }
     }
 
-    fn root_mock_values(&self) -> &Vec<BTreeMap<String, Self::Value>> {
-        &self.root_mock_values
+    fn get_macro(&self, name: String) -> Option<&dyn MacroHandler> {
+        self.macros.get(&name).map(|m| m.as_ref())
     }
 
-    fn mock_values(&self) -> &HashMap<SqlCompositionAlias, Vec<BTreeMap<String, Self::Value>>> {
-        &self.mock_values
+    fn insert_macro(&mut self, name: String, handler: Box<dyn MacroHandler>) {
+        self.macros.insert(name.to_lowercase(), handler);
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Composer, ComposerConnection, PostgresComposer};
+    use super::{Composer, ComposerConnection, ComposerContext, PostgresComposer};
+    use crate::composer::value_binds;
 
     use crate::{bind_values, mock_db_object_values, mock_path_values, mock_values};
 
@@ -162,7 +578,13 @@ mod tests {
     use postgres::types::ToSql;
     use postgres::{Connection, TlsMode};
 
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap};
+
+    use crate::types::value::Value;
+
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use serde_json::json;
 
     use dotenv::dotenv;
     use std::env;
@@ -213,15 +635,15 @@ mod tests {
 
         assert_eq!(remaining.fragment, "", "insert stmt nothing remaining");
 
-        let mut composer = PostgresComposer::new();
+        let composer = PostgresComposer::new();
 
-        composer.values = bind_values!(&dyn ToSql:
+        let bind_values = bind_values!(&dyn ToSql:
                                        "name" => [&person.name],
                                        "data" => [&person.data]
         );
 
         let (bound_sql, bindings) = composer
-            .compose(&insert_stmt.item)
+            .compose(&insert_stmt.item, &bind_values, &HashMap::new())
             .expect("compose should work");
 
         let expected_bound_sql = "INSERT INTO person (name, data) VALUES ( $1, $2 );";
@@ -235,12 +657,13 @@ mod tests {
         assert_eq!(remaining.fragment, "", "select stmt nothing remaining");
 
         let (bound_sql, bindings) = composer
-            .compose(&select_stmt.item)
+            .compose(&select_stmt.item, &bind_values, &HashMap::new())
             .expect("compose should work");
 
-        let expected_bound_sql = "SELECT id, name, data FROM person WHERE name = $1 AND name = $2;";
+        let expected_bound_sql = "SELECT id, name, data FROM person WHERE name = $1 AND name = $1;";
 
         assert_eq!(bound_sql, expected_bound_sql, "select multi-use bindings");
+        assert_eq!(bindings.len(), 1, "select multi-use binding bound once");
 
         let stmt = conn.prepare(&bound_sql).unwrap();
 
@@ -274,9 +697,9 @@ mod tests {
 
         let stmt = SqlComposition::from_path_name("src/tests/values/simple.tql".into()).unwrap();
 
-        let mut composer = PostgresComposer::new();
+        let composer = PostgresComposer::new();
 
-        composer.values = bind_values!(&dyn ToSql:
+        let bind_values = bind_values!(&dyn ToSql:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
@@ -290,8 +713,10 @@ mod tests {
             "col_4" => &"d_value"
         });
 
-        let (bound_sql, bindings) = composer.compose(&stmt.item).expect("compose should work");
-        let (mut mock_bound_sql, mock_bindings) = composer.mock_compose(&mock_values, 0);
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+        let (mut mock_bound_sql, mock_bindings) = ComposerContext::new(&composer, &bind_values, &HashMap::new())
+            .mock_compose(&mock_values, 0)
+            .expect("mock_compose should work");
 
         mock_bound_sql.push(';');
 
@@ -320,9 +745,9 @@ mod tests {
 
         let stmt = SqlComposition::from_path_name("src/tests/values/include.tql".into()).unwrap();
 
-        let mut composer = PostgresComposer::new();
+        let composer = PostgresComposer::new();
 
-        composer.values = bind_values!(&dyn ToSql:
+        let bind_values = bind_values!(&dyn ToSql:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
@@ -343,8 +768,10 @@ mod tests {
             "col_4" => &"d_value"
         });
 
-        let (bound_sql, bindings) = composer.compose(&stmt.item).expect("compose should work");
-        let (mut mock_bound_sql, mock_bindings) = composer.mock_compose(&mock_values, 0);
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+        let (mut mock_bound_sql, mock_bindings) = ComposerContext::new(&composer, &bind_values, &HashMap::new())
+            .mock_compose(&mock_values, 0)
+            .expect("mock_compose should work");
 
         mock_bound_sql.push(';');
 
@@ -356,7 +783,7 @@ mod tests {
             values.push(get_row_values(row));
         }
 
-        let mock_prep_stmt = conn.prepare(&bound_sql).unwrap();
+        let mock_prep_stmt = conn.prepare(&mock_bound_sql).unwrap();
 
         let mut mock_values: Vec<Vec<String>> = vec![];
 
@@ -364,7 +791,9 @@ mod tests {
             mock_values.push(get_row_values(row));
         }
 
-        assert_eq!(bound_sql, mock_bound_sql, "preparable statements match");
+        let expected_bound_sql = "SELECT $1 AS col_1, $2 AS col_2, $3 AS col_3, $4 AS col_4 UNION ALL SELECT $4 AS col_1, $3 AS col_2, $5 AS col_3, $2 AS col_4;";
+
+        assert_eq!(bound_sql, expected_bound_sql, "repeated bindings reuse their placeholder");
         assert_eq!(values, mock_values, "exected values");
     }
 
@@ -375,9 +804,9 @@ mod tests {
         let stmt =
             SqlComposition::from_path_name("src/tests/values/double-include.tql".into()).unwrap();
 
-        let mut composer = PostgresComposer::new();
+        let composer = PostgresComposer::new();
 
-        composer.values = bind_values!(&dyn ToSql:
+        let bind_values = bind_values!(&dyn ToSql:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
@@ -405,8 +834,10 @@ mod tests {
             "col_4" => &"d_value"
         });
 
-        let (bound_sql, bindings) = composer.compose(&stmt.item).expect("compose should work");
-        let (mut mock_bound_sql, mock_bindings) = composer.mock_compose(&mock_values, 0);
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+        let (mut mock_bound_sql, mock_bindings) = ComposerContext::new(&composer, &bind_values, &HashMap::new())
+            .mock_compose(&mock_values, 0)
+            .expect("mock_compose should work");
 
         mock_bound_sql.push(';');
 
@@ -418,7 +849,7 @@ mod tests {
             values.push(get_row_values(row));
         }
 
-        let mock_prep_stmt = conn.prepare(&bound_sql).unwrap();
+        let mock_prep_stmt = conn.prepare(&mock_bound_sql).unwrap();
 
         let mut mock_values: Vec<Vec<String>> = vec![];
 
@@ -426,7 +857,9 @@ mod tests {
             mock_values.push(get_row_values(row));
         }
 
-        assert_eq!(bound_sql, mock_bound_sql, "preparable statements match");
+        let expected_bound_sql = "SELECT $1 AS col_1, $2 AS col_2, $3 AS col_3, $4 AS col_4 UNION ALL SELECT $5 AS col_1, $1 AS col_2, $3 AS col_3, $4 AS col_4 UNION ALL SELECT $4 AS col_1, $3 AS col_2, $6 AS col_3, $1 AS col_4;";
+
+        assert_eq!(bound_sql, expected_bound_sql, "repeated bindings reuse their placeholder");
         assert_eq!(values, mock_values, "exected values");
     }
 
@@ -436,16 +869,16 @@ mod tests {
 
         let (_remaining, stmt) = parse_template(Span::new("SELECT col_1, col_2, col_3, col_4 FROM (:compose(src/tests/values/double-include.tql)) AS main WHERE col_1 in (:bind(col_1_values EXPECTING MIN 1)) AND col_3 IN (:bind(col_3_values EXPECTING MIN 1));".into()), None).unwrap();
 
-        let expected_sql = "SELECT col_1, col_2, col_3, col_4 FROM ( SELECT $1 AS col_1, $2 AS col_2, $3 AS col_3, $4 AS col_4 UNION ALL SELECT $5 AS col_1, $6 AS col_2, $7 AS col_3, $8 AS col_4 UNION ALL SELECT $9 AS col_1, $10 AS col_2, $11 AS col_3, $12 AS col_4 ) AS main WHERE col_1 in ( $13, $14 ) AND col_3 IN ( $15, $16 );";
+        let expected_sql = "SELECT col_1, col_2, col_3, col_4 FROM ( SELECT $1 AS col_1, $2 AS col_2, $3 AS col_3, $4 AS col_4 UNION ALL SELECT $5 AS col_1, $1 AS col_2, $3 AS col_3, $4 AS col_4 UNION ALL SELECT $4 AS col_1, $3 AS col_2, $6 AS col_3, $1 AS col_4 ) AS main WHERE col_1 in ( $7, $8 ) AND col_3 IN ( $9, $10 );";
 
         let expected_values = vec![
             vec!["d_value", "f_value", "b_value", "a_value"],
             vec!["a_value", "b_value", "c_value", "d_value"],
         ];
 
-        let mut composer = PostgresComposer::new();
+        let composer = PostgresComposer::new();
 
-        composer.values = bind_values!(&dyn ToSql:
+        let bind_values = bind_values!(&dyn ToSql:
                                        "a" => [&"a_value"],
                                        "b" => [&"b_value"],
                                        "c" => [&"c_value"],
@@ -456,7 +889,7 @@ mod tests {
                                        "col_3_values" => [&"b_value", &"c_value"]
         );
 
-        let (bound_sql, bindings) = composer.compose(&stmt.item).expect("compose should work");
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
 
         assert_eq!(bound_sql, expected_sql, "preparable statements match");
 
@@ -480,11 +913,11 @@ mod tests {
         )
         .unwrap();
 
-        let expected_bound_sql = "SELECT COUNT(1) FROM ( SELECT $1 AS col_1, $2 AS col_2, $3 AS col_3, $4 AS col_4 UNION ALL SELECT $5 AS col_1, $6 AS col_2, $7 AS col_3, $8 AS col_4 UNION ALL SELECT $9 AS col_1, $10 AS col_2, $11 AS col_3, $12 AS col_4 ) AS count_main";
+        let expected_bound_sql = "SELECT COUNT(1) FROM ( SELECT $1 AS col_1, $2 AS col_2, $3 AS col_3, $4 AS col_4 UNION ALL SELECT $5 AS col_1, $1 AS col_2, $3 AS col_3, $4 AS col_4 UNION ALL SELECT $4 AS col_1, $3 AS col_2, $6 AS col_3, $1 AS col_4 ) AS count_main";
 
-        let mut composer = PostgresComposer::new();
+        let composer = PostgresComposer::new();
 
-        composer.values = bind_values!(&dyn ToSql:
+        let bind_values = bind_values!(&dyn ToSql:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
@@ -495,7 +928,7 @@ mod tests {
         "col_3_values" => [&"b_value", &"c_value"]
         );
 
-        let (bound_sql, bindings) = composer.compose(&stmt.item).expect("compose should work");
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
 
         assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
 
@@ -518,11 +951,11 @@ mod tests {
 
         let (_remaining, stmt) = parse_template(Span::new(":union(src/tests/values/double-include.tql, src/tests/values/include.tql, src/tests/values/double-include.tql);".into()), None).unwrap();
 
-        let expected_bound_sql = "SELECT $1 AS col_1, $2 AS col_2, $3 AS col_3, $4 AS col_4 UNION ALL SELECT $5 AS col_1, $6 AS col_2, $7 AS col_3, $8 AS col_4 UNION ALL SELECT $9 AS col_1, $10 AS col_2, $11 AS col_3, $12 AS col_4 UNION SELECT $13 AS col_1, $14 AS col_2, $15 AS col_3, $16 AS col_4 UNION ALL SELECT $17 AS col_1, $18 AS col_2, $19 AS col_3, $20 AS col_4 UNION SELECT $21 AS col_1, $22 AS col_2, $23 AS col_3, $24 AS col_4 UNION ALL SELECT $25 AS col_1, $26 AS col_2, $27 AS col_3, $28 AS col_4 UNION ALL SELECT $29 AS col_1, $30 AS col_2, $31 AS col_3, $32 AS col_4";
+        let expected_bound_sql = "SELECT $1 AS col_1, $2 AS col_2, $3 AS col_3, $4 AS col_4 UNION ALL SELECT $5 AS col_1, $1 AS col_2, $3 AS col_3, $4 AS col_4 UNION ALL SELECT $4 AS col_1, $3 AS col_2, $6 AS col_3, $1 AS col_4 UNION SELECT $5 AS col_1, $1 AS col_2, $3 AS col_3, $4 AS col_4 UNION ALL SELECT $4 AS col_1, $3 AS col_2, $6 AS col_3, $1 AS col_4 UNION SELECT $1 AS col_1, $2 AS col_2, $3 AS col_3, $4 AS col_4 UNION ALL SELECT $5 AS col_1, $1 AS col_2, $3 AS col_3, $4 AS col_4 UNION ALL SELECT $4 AS col_1, $3 AS col_2, $6 AS col_3, $1 AS col_4";
 
-        let mut composer = PostgresComposer::new();
+        let composer = PostgresComposer::new();
 
-        composer.values = bind_values!(&dyn ToSql:
+        let bind_values = bind_values!(&dyn ToSql:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
@@ -533,7 +966,7 @@ mod tests {
         "col_3_values" => [&"b_value", &"c_value"]
         );
 
-        let (bound_sql, bindings) = composer.compose(&stmt.item).expect("compose should work");
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
 
         assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
 
@@ -556,6 +989,229 @@ mod tests {
         assert_eq!(values, expected_values, "exected values");
     }
 
+    #[test]
+    fn test_with_command() {
+        let conn = setup_db();
+
+        let (_remaining, stmt) = parse_template(Span::new(":with(simple_values of src/tests/values/simple.tql, include_values of src/tests/values/include.tql) SELECT i.col_1, i.col_2, i.col_3, i.col_4 FROM include_values AS i JOIN simple_values AS s ON s.col_1 = i.col_1 WHERE i.col_4 IN (SELECT col_4 FROM simple_values AS ss);".into()), None).unwrap();
+
+        let expected_bound_sql = "WITH simple_values AS ( SELECT $1 AS col_1, $2 AS col_2, $3 AS col_3, $4 AS col_4 ), include_values AS ( SELECT $5 AS col_1, $4 AS col_2, $2 AS col_3, $1 AS col_4 UNION ALL SELECT $1 AS col_1, $2 AS col_2, $3 AS col_3, $4 AS col_4 ) SELECT i.col_1, i.col_2, i.col_3, i.col_4 FROM include_values AS i JOIN simple_values AS s ON s.col_1 = i.col_1 WHERE i.col_4 IN (SELECT col_4 FROM simple_values AS ss )";
+
+        let composer = PostgresComposer::new();
+
+        let bind_values = bind_values!(&dyn ToSql:
+        "a" => [&"a_value"],
+        "b" => [&"b_value"],
+        "c" => [&"c_value"],
+        "d" => [&"d_value"],
+        "e" => [&"e_value"]
+        );
+
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+        assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
+
+        let prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+        let mut values: Vec<Vec<String>> = vec![];
+
+        for row in &prep_stmt.query(&bindings).unwrap() {
+            values.push(get_row_values(row));
+        }
+
+        let expected_values = vec![vec!["a_value", "b_value", "c_value", "d_value"]];
+
+        assert_eq!(values, expected_values, "exected values");
+    }
+
+    #[test]
+    fn test_paginate() {
+        let conn = setup_db();
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1, col_2, col_3, col_4 FROM (:compose(src/tests/values/double-include.tql)) AS main WHERE col_2 <> :bind(f) :paginate_after(col_1, after, limit);".into()), None).unwrap();
+
+        let expected_bound_sql = "SELECT col_1, col_2, col_3, col_4 FROM ( SELECT $1 AS col_1, $2 AS col_2, $3 AS col_3, $4 AS col_4 UNION ALL SELECT $5 AS col_1, $1 AS col_2, $3 AS col_3, $4 AS col_4 UNION ALL SELECT $4 AS col_1, $3 AS col_2, $6 AS col_3, $1 AS col_4 ) AS main WHERE col_2 <> $2 AND \"col_1\" > $7 ORDER BY \"col_1\" LIMIT $8;";
+
+        let composer = PostgresComposer::new();
+
+        let mut bind_values = bind_values!(&dyn ToSql:
+        "a" => [&"a_value"],
+        "b" => [&"b_value"],
+        "c" => [&"c_value"],
+        "d" => [&"d_value"],
+        "e" => [&"e_value"],
+        "f" => [&"f_value"],
+        "after" => [&"a_value"],
+        "limit" => [&1i64]
+        );
+
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+        assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
+
+        let prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+        let mut values: Vec<Vec<String>> = vec![];
+
+        for row in &prep_stmt.query(&bindings).unwrap() {
+            values.push(get_row_values(row));
+        }
+
+        let expected_values = vec![vec!["e_value", "d_value", "b_value", "a_value"]];
+
+        assert_eq!(values, expected_values, "exected values");
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1, col_2, col_3, col_4 FROM (:compose(src/tests/values/simple.tql)) AS main :paginate(limit, offset);".into()), None).unwrap();
+
+        bind_values.insert("offset".into(), vec![&0i64]);
+
+        let expected_bound_sql = "SELECT col_1, col_2, col_3, col_4 FROM ( SELECT $1 AS col_1, $2 AS col_2, $3 AS col_3, $4 AS col_4 ) AS main LIMIT $5 OFFSET $6;";
+
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+        assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
+
+        let prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+        let mut values: Vec<Vec<String>> = vec![];
+
+        for row in &prep_stmt.query(&bindings).unwrap() {
+            values.push(get_row_values(row));
+        }
+
+        let expected_values = vec![vec!["a_value", "b_value", "c_value", "d_value"]];
+
+        assert_eq!(values, expected_values, "exected values");
+    }
+
+    #[test]
+    fn test_array_bindings_multiple_values() {
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1 FROM t AS main WHERE col_1 IN (:bind(col_1_values EXPECTING MIN 1 MAX 3)) AND col_3 NOT IN (:bind(col_3_values EXPECTING MIN 1));".into()), None).unwrap();
+
+        let col_3_values = vec!["c_value"];
+
+        let mut composer = PostgresComposer::new();
+
+        composer.set_array_bindings(true);
+
+        let mut bind_values = bind_values!(&dyn ToSql:
+        "col_1_values" => [&"d_value", &"a_value"],
+        "col_3_values" => [&col_3_values]
+        );
+
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+        assert_eq!(
+            bound_sql,
+            "SELECT col_1 FROM t AS main WHERE col_1 IN ( $1, $2 ) AND col_3 <> ALL($3);",
+            "several values keep the IN list"
+        );
+        assert_eq!(bindings.len(), 3, "values of the list and the array");
+
+        bind_values.insert("col_1_values".into(), vec![&"a_value"]);
+
+        let (bound_sql, _bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+        assert_eq!(
+            bound_sql,
+            "SELECT col_1 FROM t AS main WHERE col_1 IN ( $1 ) AND col_3 <> ALL($2);",
+            "a single scalar keeps the IN list"
+        );
+
+        bind_values.insert(
+            "col_1_values".into(),
+            vec![&"a_value", &"b_value", &"c_value", &"d_value"],
+        );
+
+        assert!(
+            composer.compose(&stmt.item, &bind_values, &HashMap::new()).is_err(),
+            "more values than EXPECTING allows"
+        );
+    }
+
+    #[test]
+    fn test_bind_rows() {
+        let conn = setup_db();
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1, col_2, col_3, col_4 FROM (:compose(src/tests/values/include.tql)) AS main WHERE (col_1, col_2) IN (:bind_rows(pairs COLUMNS col_1, col_2));".into()), None).unwrap();
+
+        let expected_bound_sql = "SELECT col_1, col_2, col_3, col_4 FROM ( SELECT $1 AS col_1, $2 AS col_2, $3 AS col_3, $4 AS col_4 UNION ALL SELECT $4 AS col_1, $3 AS col_2, $5 AS col_3, $2 AS col_4 ) AS main WHERE (col_1, col_2) IN ( ($6, $7), ($8, $9) );";
+
+        let composer = PostgresComposer::new();
+
+        let bind_values = bind_values!(&dyn ToSql:
+        "a" => [&"a_value"],
+        "b" => [&"b_value"],
+        "c" => [&"c_value"],
+        "d" => [&"d_value"],
+        "e" => [&"e_value"],
+        "pairs" => [&"a_value", &"b_value", &"e_value", &"b_value"]
+        );
+
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+        assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
+
+        let prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+        let mut values: Vec<Vec<String>> = vec![];
+
+        for row in &prep_stmt.query(&bindings).unwrap() {
+            values.push(get_row_values(row));
+        }
+
+        let expected_values = vec![vec!["a_value", "b_value", "c_value", "d_value"]];
+
+        assert_eq!(values, expected_values, "exected values");
+    }
+
+    #[test]
+    fn test_array_bindings() {
+        let conn = setup_db();
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1, col_2, col_3, col_4 FROM (:compose(src/tests/values/double-include.tql)) AS main WHERE col_1 IN (:bind(col_1_values EXPECTING MIN 1)) AND col_3 NOT IN (:bind(col_3_values EXPECTING MIN 1)) AND col_4 IN (:bind(a));".into()), None).unwrap();
+
+        let expected_bound_sql = "SELECT col_1, col_2, col_3, col_4 FROM ( SELECT $1 AS col_1, $2 AS col_2, $3 AS col_3, $4 AS col_4 UNION ALL SELECT $5 AS col_1, $1 AS col_2, $3 AS col_3, $4 AS col_4 UNION ALL SELECT $4 AS col_1, $3 AS col_2, $6 AS col_3, $1 AS col_4 ) AS main WHERE col_1 = ANY($7) AND col_3 <> ALL($8) AND col_4 IN ( $4 );";
+
+        let col_1_values = vec!["d_value", "a_value", "e_value"];
+        let col_3_values = vec!["c_value"];
+
+        let mut composer = PostgresComposer::new();
+
+        composer.set_array_bindings(true);
+
+        let bind_values = bind_values!(&dyn ToSql:
+        "a" => [&"a_value"],
+        "b" => [&"b_value"],
+        "c" => [&"c_value"],
+        "d" => [&"d_value"],
+        "e" => [&"e_value"],
+        "f" => [&"f_value"],
+        "col_1_values" => [&col_1_values],
+        "col_3_values" => [&col_3_values]
+        );
+
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+        assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
+        assert_eq!(bindings.len(), 8, "one value per array");
+
+        let prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+        let mut values: Vec<Vec<String>> = vec![];
+
+        for row in &prep_stmt.query(&bindings).unwrap() {
+            values.push(get_row_values(row));
+        }
+
+        let expected_values = vec![
+            vec!["d_value", "f_value", "b_value", "a_value"],
+            vec!["e_value", "d_value", "b_value", "a_value"],
+        ];
+
+        assert_eq!(values, expected_values, "exected values");
+    }
+
     #[test]
     fn test_include_mock_multi_value_bind() {
         let conn = setup_db();
@@ -569,9 +1225,9 @@ mod tests {
             vec!["ee_value", "dd_value", "bb_value", "aa_value"],
         ];
 
-        let mut composer = PostgresComposer::new();
+        let composer = PostgresComposer::new();
 
-        composer.values = bind_values!(&dyn ToSql:
+        let bind_values = bind_values!(&dyn ToSql:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
@@ -582,14 +1238,14 @@ mod tests {
         "col_3_values" => [&"bb_value", &"b_value"]
         );
 
-        composer.mock_values = mock_path_values!(&dyn ToSql: "src/tests/values/include.tql" => [{
+        let mock_values = mock_path_values!(&dyn ToSql: "src/tests/values/include.tql" => [{
         "col_1" => &"ee_value",
         "col_2" => &"dd_value",
         "col_3" => &"bb_value",
         "col_4" => &"aa_value"
         }]);
 
-        let (bound_sql, bindings) = composer
+        let (bound_sql, bindings) = ComposerContext::new(&composer, &bind_values, &mock_values)
             .compose_statement(&stmt, 1, false)
             .expect("compose_statement should work");
 
@@ -619,9 +1275,9 @@ mod tests {
             vec!["aa_value", "bb_value", "cc_value", "dd_value"],
         ];
 
-        let mut composer = PostgresComposer::new();
+        let composer = PostgresComposer::new();
 
-        composer.values = bind_values!(&dyn ToSql:
+        let bind_values = bind_values!(&dyn ToSql:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
@@ -632,7 +1288,7 @@ mod tests {
         "col_3_values" => [&"bb_value", &"cc_value"]
         );
 
-        composer.mock_values = mock_path_values!(&dyn ToSql: "src/tests/values/double-include.tql" => [
+        let mock_values = mock_path_values!(&dyn ToSql: "src/tests/values/double-include.tql" => [
                     {
                     "col_1" => &"dd_value",
                     "col_2" => &"ff_value",
@@ -654,7 +1310,7 @@ mod tests {
 
         ]);
 
-        let (bound_sql, bindings) = composer
+        let (bound_sql, bindings) = ComposerContext::new(&composer, &bind_values, &mock_values)
             .compose_statement(&stmt, 1, false)
             .expect("compose_statement should work");
 
@@ -684,9 +1340,9 @@ mod tests {
             vec!["aa_value", "bb_value", "cc_value", "dd_value"],
         ];
 
-        let mut composer = PostgresComposer::new();
+        let composer = PostgresComposer::new();
 
-        composer.values = bind_values!(&dyn ToSql:
+        let bind_values = bind_values!(&dyn ToSql:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
@@ -717,9 +1373,7 @@ mod tests {
             "col_4" => &"dd_value"
         }]);
 
-        composer.mock_values = mock_values;
-
-        let (bound_sql, bindings) = composer
+        let (bound_sql, bindings) = ComposerContext::new(&composer, &bind_values, &mock_values)
             .compose_statement(&stmt, 1, false)
             .expect("compose_statement should work");
 
@@ -741,15 +1395,13 @@ mod tests {
 
         let stmt = SqlComposition::from_path_name("src/tests/values/simple.tql".into()).unwrap();
 
-        let bind_values = bind_values!(&dyn ToSql:
-        "a" => [&"a_value"],
-        "b" => [&"b_value"],
-        "c" => [&"c_value"],
-        "d" => [&"d_value"]
-        );
-
         let (prep_stmt, bindings) = conn
-            .compose(&stmt.item, bind_values, vec![], HashMap::new())
+            .composition(&stmt.item)
+            .bind("a", &"a_value")
+            .bind("b", &"b_value")
+            .bind("c", &"c_value")
+            .bind("d", &"d_value")
+            .prepare()
             .unwrap();
 
         let mut values: Vec<Vec<String>> = vec![];
@@ -767,4 +1419,170 @@ mod tests {
 
         assert_eq!(values, expected, "exected values");
     }
+
+    #[test]
+    fn test_owned_values() {
+        let conn = setup_db();
+
+        conn.execute("DROP TABLE IF EXISTS event;", &[]).unwrap();
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS event (
+                        id              INTEGER PRIMARY KEY,
+                        active          BOOLEAN NOT NULL,
+                        day             DATE NOT NULL,
+                        amount          NUMERIC NOT NULL,
+                        doc             JSONB NOT NULL
+                      )",
+            &[],
+        )
+        .unwrap();
+
+        let (_remaining, insert_stmt) = parse_template(Span::new("INSERT INTO event (id, active, day, amount, doc) VALUES (:bind(id), :bind(active), :bind(day), :bind(amount), :bind(doc));".into()), None).unwrap();
+
+        let mut values: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+
+        values.insert("id".to_string(), vec![Value::Integer(1)]);
+        values.insert("active".to_string(), vec![Value::Bool(true)]);
+        values.insert(
+            "day".to_string(),
+            vec![Value::Date(NaiveDate::from_ymd_opt(2019, 7, 4).unwrap())],
+        );
+        values.insert(
+            "amount".to_string(),
+            vec![Value::Decimal(Decimal::new(-123456, 3))],
+        );
+        values.insert(
+            "doc".to_string(),
+            vec![Value::Json(json!({"name": "O'Brien"}))],
+        );
+
+        let composer = PostgresComposer::new();
+
+        let bind_values = value_binds(&values);
+
+        let (bound_sql, bindings) = composer
+            .compose(&insert_stmt.item, &bind_values, &HashMap::new())
+            .expect("compose should work");
+
+        conn.execute(&bound_sql, &bindings).unwrap();
+
+        let (_remaining, select_stmt) = parse_template(Span::new("SELECT active, day::TEXT, amount::TEXT, doc->>'name' FROM event WHERE id = :bind(id) AND day = :bind(day) AND amount = :bind(amount);".into()), None).unwrap();
+
+        let (bound_sql, bindings) = composer
+            .compose(&select_stmt.item, &bind_values, &HashMap::new())
+            .expect("compose should work");
+
+        let stmt = conn.prepare(&bound_sql).unwrap();
+
+        let mut rows: Vec<(bool, String, String, String)> = vec![];
+
+        for row in &stmt.query(&bindings).unwrap() {
+            rows.push((row.get(0), row.get(1), row.get(2), row.get(3)));
+        }
+
+        assert_eq!(
+            rows,
+            vec![(
+                true,
+                "2019-07-04".to_string(),
+                "-123.456".to_string(),
+                "O'Brien".to_string()
+            )],
+            "typed values round trip"
+        );
+    }
+
+    #[cfg(feature = "composer-serde")]
+    #[test]
+    fn test_row_value_types() {
+        use super::row_value;
+
+        use chrono::{FixedOffset, TimeZone};
+        use serde_value::Value as SerdeValueEnum;
+        use uuid::Uuid;
+
+        let conn = setup_db();
+
+        conn.execute("DROP TABLE IF EXISTS typed;", &[]).unwrap();
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS typed (
+                        data            BYTEA NOT NULL,
+                        amount          NUMERIC NOT NULL,
+                        day             DATE NOT NULL,
+                        at              TIME NOT NULL,
+                        ts              TIMESTAMP NOT NULL,
+                        ts_tz           TIMESTAMPTZ NOT NULL,
+                        uid             UUID NOT NULL,
+                        doc             JSON NOT NULL,
+                        doc_b           JSONB NOT NULL,
+                        missing         NUMERIC
+                      )",
+            &[],
+        )
+        .unwrap();
+
+        let (_remaining, insert_stmt) = parse_template(Span::new("INSERT INTO typed (data, amount, day, at, ts, ts_tz, uid, doc, doc_b, missing) VALUES (:bind(data), :bind(amount), :bind(day), :bind(at), :bind(ts), :bind(ts_tz), :bind(uid), :bind(doc), :bind(doc_b), :bind(missing));".into()), None).unwrap();
+
+        let day = NaiveDate::from_ymd_opt(2019, 7, 4).unwrap();
+        let ts = day.and_hms_opt(12, 30, 15).unwrap();
+
+        let mut values: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+
+        values.insert("data".to_string(), vec![Value::Blob(vec![0, 159, 146, 150])]);
+        values.insert("amount".to_string(), vec![Value::Decimal(Decimal::new(-123456, 3))]);
+        values.insert("day".to_string(), vec![Value::Date(day)]);
+        values.insert("at".to_string(), vec![Value::Time(ts.time())]);
+        values.insert("ts".to_string(), vec![Value::Timestamp(ts)]);
+        values.insert(
+            "ts_tz".to_string(),
+            vec![Value::TimestampTz(
+                FixedOffset::east_opt(3600).unwrap().from_local_datetime(&ts).unwrap(),
+            )],
+        );
+        values.insert(
+            "uid".to_string(),
+            vec![Value::Uuid(Uuid::parse_str("936da01f-9abd-4d9d-80c7-02af85c822a8").unwrap())],
+        );
+        values.insert("doc".to_string(), vec![Value::Json(json!({"name": "O'Brien"}))]);
+        values.insert("doc_b".to_string(), vec![Value::Json(json!({"name": "O'Brien"}))]);
+        values.insert("missing".to_string(), vec![Value::Null]);
+
+        let composer = PostgresComposer::new();
+
+        let bind_values = value_binds(&values);
+
+        let (bound_sql, bindings) = composer
+            .compose(&insert_stmt.item, &bind_values, &HashMap::new())
+            .expect("compose should work");
+
+        conn.execute(&bound_sql, &bindings).unwrap();
+
+        let stmt = conn.prepare("SELECT * FROM typed;").unwrap();
+
+        let rows = stmt.query(&[]).unwrap();
+
+        let row = row_value(&rows.get(0)).expect("row_value should work");
+
+        let text = |t: &str| SerdeValueEnum::String(t.to_string());
+
+        let expected: BTreeMap<SerdeValueEnum, SerdeValueEnum> = vec![
+            ("data", SerdeValueEnum::Bytes(vec![0, 159, 146, 150])),
+            ("amount", text("-123.456")),
+            ("day", text("2019-07-04")),
+            ("at", text("12:30:15")),
+            ("ts", text("2019-07-04 12:30:15")),
+            ("ts_tz", text("2019-07-04 11:30:15+00:00")),
+            ("uid", text("936da01f-9abd-4d9d-80c7-02af85c822a8")),
+            ("doc", text(r#"{"name":"O'Brien"}"#)),
+            ("doc_b", text(r#"{"name": "O'Brien"}"#)),
+            ("missing", SerdeValueEnum::Unit),
+        ]
+        .into_iter()
+        .map(|(name, v)| (text(name), v))
+        .collect();
+
+        assert_eq!(row, SerdeValueEnum::Map(expected), "values read back in the other drivers' forms");
+    }
 }
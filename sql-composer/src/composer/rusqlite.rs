@@ -1,18 +1,23 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::HashMap;
+use std::marker::PhantomData;
 
-#[cfg(feature = "composer-serde")]
-use rusqlite::types::ToSqlOutput;
+use rusqlite::types::{ToSqlOutput, Value as SqliteValue, ValueRef};
 use rusqlite::{Connection, Statement};
 
 pub use rusqlite::types::{Null, ToSql};
 
-use super::{Composer, ComposerConfig, ComposerConnection};
+use super::{BindValue, Binds, BindingTags, Composed, Composer, ComposerConfig, ComposerConnection,
+            ComposerContext, MacroHandler, Mocks};
 
-use crate::types::{ParsedItem, SqlComposition, SqlCompositionAlias};
+use crate::types::value::Value as ComposerValue;
+use crate::types::SqlComposition;
 
 #[cfg(feature = "composer-serde")]
 use crate::types::SerdeValue;
 
+#[cfg(feature = "composer-serde")]
+use rusqlite::Row;
+
 #[cfg(feature = "composer-serde")]
 use serde_value::Value;
 
@@ -24,27 +29,62 @@ impl<'a> ComposerConnection<'a> for Connection {
     type Value = &'a (dyn ToSql + 'a);
     type Statement = Statement<'a>;
 
-    fn compose(
-        &'a self,
-        s: &SqlComposition,
-        values: BTreeMap<String, Vec<Self::Value>>,
-        root_mock_values: Vec<BTreeMap<String, Self::Value>>,
-        mock_values: HashMap<SqlCompositionAlias, Vec<BTreeMap<String, Self::Value>>>,
-    ) -> Result<(Self::Statement, Vec<Self::Value>), ()> {
-        let c = RusqliteComposer {
-            #[allow(dead_code)]
-            config: RusqliteComposer::config(),
-            values,
-            root_mock_values,
-            mock_values,
-        };
+    fn composer(&self) -> Self::Composer {
+        RusqliteComposer::new()
+    }
+
+    fn prepare_sql(&'a self, sql: &str) -> Result<Self::Statement, ()> {
+        //TODO: support a DriverError type to handle this better
+        self.prepare(sql).map_err(|_| ())
+    }
 
-        let (sql, bind_vars) = c.compose(s)?;
+    #[cfg(feature = "composer-serde")]
+    fn query_values(
+        &self,
+        stmt: &mut Self::Statement,
+        values: &[Self::Value],
+    ) -> Result<Vec<Value>, ()> {
+        let column_names: Vec<String> = stmt
+            .column_names()
+            .into_iter()
+            .map(String::from)
+            .collect();
 
         //TODO: support a DriverError type to handle this better
-        let stmt = self.prepare(&sql).or_else(|_| Err(()))?;
+        let rows = stmt
+            .query_map(values, |row| Ok(row_value(&column_names, row)))
+            .map_err(|_| ())?;
+
+        rows.map(|row| row.map_err(|_| ())).collect()
+    }
+}
+
+/// Maps a row to a map of its column names and values, which can be deserialized into any type.
+/// A blob is always read as bytes, even when it holds valid UTF-8.
+#[cfg(feature = "composer-serde")]
+pub fn row_value(column_names: &[String], row: &Row) -> Value {
+    let columns = column_names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let v = match row.get_raw(i) {
+                ValueRef::Null => Value::Unit,
+                ValueRef::Integer(int) => Value::I64(int),
+                ValueRef::Real(r) => Value::F64(r),
+                ValueRef::Text(t) => Value::String(t.to_string()),
+                ValueRef::Blob(b) => Value::Bytes(b.to_vec()),
+            };
+
+            (Value::String(name.to_string()), v)
+        })
+        .collect();
+
+    Value::Map(columns)
+}
 
-        Ok((stmt, bind_vars))
+impl<'a, T: ToSql> BindValue<'a, &'a (dyn ToSql + 'a)> for T {
+    fn bind_value(&'a self) -> &'a (dyn ToSql + 'a) {
+        self
     }
 }
 
@@ -60,21 +100,94 @@ impl ToSql for SerdeValue {
     }
 }
 
+/// Types SQLite doesn't have are bound as text, in the formats its date and time functions read.
+impl ToSql for ComposerValue {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(match self {
+            ComposerValue::Null => ToSqlOutput::from(Null),
+            ComposerValue::Bool(b) => ToSqlOutput::from(*b),
+            ComposerValue::Integer(i) => ToSqlOutput::from(*i),
+            ComposerValue::Real(f) => ToSqlOutput::from(*f),
+            ComposerValue::Decimal(d) => ToSqlOutput::from(d.to_string()),
+            ComposerValue::Text(t) => ToSqlOutput::from(t.as_str()),
+            ComposerValue::Blob(b) => ToSqlOutput::from(b.as_slice()),
+            ComposerValue::Date(d) => ToSqlOutput::from(d.format("%Y-%m-%d").to_string()),
+            ComposerValue::Time(t) => ToSqlOutput::from(t.format("%H:%M:%S%.f").to_string()),
+            ComposerValue::Timestamp(ts) => {
+                ToSqlOutput::from(ts.format("%Y-%m-%d %H:%M:%S%.f").to_string())
+            }
+            ComposerValue::TimestampTz(ts) => {
+                ToSqlOutput::from(ts.format("%Y-%m-%d %H:%M:%S%.f%:z").to_string())
+            }
+            ComposerValue::Uuid(u) => ToSqlOutput::from(u.to_hyphenated().to_string()),
+            ComposerValue::Json(j) => ToSqlOutput::from(j.to_string()),
+        })
+    }
+}
+
 pub struct RusqliteComposer<'a> {
-    pub config:           ComposerConfig,
-    pub values:           BTreeMap<String, Vec<&'a dyn ToSql>>,
-    pub root_mock_values: Vec<BTreeMap<String, &'a dyn ToSql>>,
-    pub mock_values:      HashMap<SqlCompositionAlias, Vec<BTreeMap<String, &'a dyn ToSql>>>,
+    pub config:      ComposerConfig,
+    pub macros:      HashMap<String, Box<dyn MacroHandler>>,
+    named:           bool,
+    max_bind_params: usize,
+    values:          PhantomData<&'a ()>,
 }
 
 impl<'a> RusqliteComposer<'a> {
     pub fn new() -> Self {
         Self {
-            config:           Self::config(),
-            values:           BTreeMap::new(),
-            root_mock_values: vec![],
-            mock_values:      HashMap::new(),
+            config:          Self::config(),
+            macros:          HashMap::new(),
+            named:           false,
+            max_bind_params: default_max_bind_params(),
+            values:          PhantomData,
+        }
+    }
+
+    /// Sets the most bind parameters in one statement, for a SQLite compiled with its own
+    /// SQLITE_MAX_VARIABLE_NUMBER.
+    pub fn set_max_bind_params(&mut self, max: usize) {
+        self.max_bind_params = max;
+    }
+
+    /// Composes named placeholders instead of `?N`. A name bound to a single value is composed as
+    /// `:name` everywhere it is used, while each value of a multi-valued binding, row binding or
+    /// mock gets its position appended, as in `:ids_3`.
+    pub fn set_named_placeholders(&mut self, enabled: bool) {
+        self.named = enabled;
+    }
+
+    /// Composes `s` with named placeholders, pairing each value with its placeholder for
+    /// rusqlite's `query_named()` and `execute_named()`.
+    pub fn compose_named(
+        &self,
+        s: &SqlComposition,
+        values: &Binds<&'a dyn ToSql>,
+        mocks: &Mocks<&'a dyn ToSql>,
+    ) -> Result<Composed<(String, &'a dyn ToSql)>, ()> {
+        if !self.named {
+            //TODO: useful error
+            return Err(());
         }
+
+        let context = ComposerContext::new(self, values, mocks);
+
+        let (sql, values) = context.compose(s)?;
+
+        let named_values = context.binding_tags().named_values(values)?;
+
+        Ok((sql, named_values))
+    }
+}
+
+/// The default SQLITE_MAX_VARIABLE_NUMBER of the linked SQLite, which was raised from 999 to 32766
+/// in SQLite 3.32.0.
+fn default_max_bind_params() -> usize {
+    if rusqlite::version_number() >= 3_032_000 {
+        32766
+    }
+    else {
+        999
     }
 }
 
@@ -85,42 +198,45 @@ impl<'a> Composer for RusqliteComposer<'a> {
         ComposerConfig { start: 0 }
     }
 
-    fn binding_tag(&self, u: usize, _name: String) -> String {
-        format!("?{}", u)
+    fn binding_tag(&self, u: usize, name: String, tags: &mut BindingTags) -> String {
+        if !self.named {
+            return format!("?{}", u);
+        }
+
+        format!(":{}", tags.named_tag(u, &name))
     }
 
-    fn compose_count_command(
-        &self,
-        composition: &ParsedItem<SqlComposition>,
-        offset: usize,
-        child: bool,
-    ) -> Result<(String, Vec<Self::Value>), ()> {
-        self.compose_count_default_command(composition, offset, child)
+    fn reuse_binding_tag(&self, u: usize, name: &str, tags: &mut BindingTags) -> Option<String> {
+        if !self.named {
+            return None;
+        }
+
+        tags.reuse_named_tag(u, name).map(|tag| format!(":{}", tag))
     }
 
-    fn compose_union_command(
-        &self,
-        composition: &ParsedItem<SqlComposition>,
-        offset: usize,
-        child: bool,
-    ) -> Result<(String, Vec<Self::Value>), ()> {
-        self.compose_union_default_command(composition, offset, child)
+    fn max_bind_params(&self) -> usize {
+        self.max_bind_params
     }
 
-    fn get_values(&self, name: String) -> Option<&Vec<Self::Value>> {
-        self.values.get(&name)
+    /// A negative LIMIT is no limit at all in SQLite.
+    fn unbounded_limit(&self) -> Option<&str> {
+        Some("-1")
     }
 
-    fn insert_value(&mut self, name: String, values: Vec<Self::Value>) -> () {
-        self.values.insert(name, values);
+    fn value_text(&self, value: &Self::Value) -> Result<String, ()> {
+        match value.to_sql() {
+            Ok(ToSqlOutput::Borrowed(ValueRef::Text(t))) => Ok(t.to_string()),
+            Ok(ToSqlOutput::Owned(SqliteValue::Text(t))) => Ok(t),
+            _ => Err(()),
+        }
     }
 
-    fn root_mock_values(&self) -> &Vec<BTreeMap<String, Self::Value>> {
-        &self.root_mock_values
+    fn get_macro(&self, name: String) -> Option<&dyn MacroHandler> {
+        self.macros.get(&name).map(|m| m.as_ref())
     }
 
-    fn mock_values(&self) -> &HashMap<SqlCompositionAlias, Vec<BTreeMap<String, Self::Value>>> {
-        &self.mock_values
+    fn insert_macro(&mut self, name: String, handler: Box<dyn MacroHandler>) {
+        self.macros.insert(name.to_lowercase(), handler);
     }
 }
 
@@ -128,9 +244,10 @@ impl<'a> Composer for RusqliteComposer<'a> {
 mod tests {
     use crate::{bind_values, mock_db_object_values, mock_path_values, mock_values};
 
-    use super::{Composer, ComposerConnection, RusqliteComposer};
+    use super::{Composer, ComposerConnection, ComposerContext, MacroHandler, RusqliteComposer};
 
-    use crate::types::{Span, SqlComposition, SqlCompositionAlias, SqlDbObject};
+    use crate::types::{Span, SqlBinding, SqlComposition, SqlCompositionAlias, SqlDbObject,
+                       SqlPagination};
 
     use crate::parser::parse_template;
 
@@ -140,7 +257,23 @@ mod tests {
 
     use rusqlite::types::ToSql;
 
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap};
+
+    use crate::composer::direct::DirectComposer;
+    use crate::composer::value_binds;
+    use crate::types::value::Value;
+
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+
+    /// Values bound by name for one case of a table driven test.
+    type CaseValues<'a> = Vec<(&'a str, Vec<&'a dyn ToSql>)>;
+
+    /// The rows a case of a table driven test expects, by column.
+    type CaseRows<'a> = Vec<Vec<&'a str>>;
+
+    /// A value bound to one name, if any, for one case of a table driven test.
+    type CaseValue<'a> = Option<Vec<&'a dyn ToSql>>;
 
     #[derive(Debug, PartialEq)]
     struct Person {
@@ -182,16 +315,16 @@ mod tests {
 
         assert_eq!(remaining.fragment, "", "nothing remaining");
 
-        let mut composer = RusqliteComposer::new();
+        let composer = RusqliteComposer::new();
 
-        composer.values = bind_values!(&dyn ToSql:
+        let bind_values = bind_values!(&dyn ToSql:
         "name" => [&person.name],
         "time_created" => [&person.time_created],
         "data" => [&person.data]
         );
 
         let (bound_sql, bindings) = composer
-            .compose(&insert_stmt.item)
+            .compose(&insert_stmt.item, &bind_values, &HashMap::new())
             .expect("compose should work");
 
         let expected_bound_sql =
@@ -206,7 +339,7 @@ mod tests {
         assert_eq!(remaining.fragment, "", "nothing remaining");
 
         let (bound_sql, bindings) = composer
-            .compose(&select_stmt.item)
+            .compose(&select_stmt.item, &bind_values, &HashMap::new())
             .expect("compose should work");
 
         let expected_bound_sql = "SELECT id, name, time_created, data FROM person WHERE name = ?1 AND time_created = ?2 AND name = ?3 AND time_created = ?4";
@@ -258,9 +391,9 @@ mod tests {
 
         let stmt = SqlComposition::from_path_name("src/tests/values/simple.tql".into()).unwrap();
 
-        let mut composer = RusqliteComposer::new();
+        let composer = RusqliteComposer::new();
 
-        composer.values = bind_values!(&dyn ToSql:
+        let bind_values = bind_values!(&dyn ToSql:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
@@ -274,8 +407,10 @@ mod tests {
             "col_4" => &"d_value"
         });
 
-        let (bound_sql, bindings) = composer.compose(&stmt.item).expect("compose should work");
-        let (mut mock_bound_sql, mock_bindings) = composer.mock_compose(&mock_values, 0);
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+        let (mut mock_bound_sql, mock_bindings) = ComposerContext::new(&composer, &bind_values, &HashMap::new())
+            .mock_compose(&mock_values, 0)
+            .expect("mock_compose should work");
 
         mock_bound_sql.push(';');
 
@@ -332,9 +467,9 @@ mod tests {
 
         let stmt = SqlComposition::from_path_name("src/tests/values/include.tql".into()).unwrap();
 
-        let mut composer = RusqliteComposer::new();
+        let composer = RusqliteComposer::new();
 
-        composer.values = bind_values!(&dyn ToSql:
+        let bind_values = bind_values!(&dyn ToSql:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
@@ -355,8 +490,10 @@ mod tests {
             "col_4" => &"d_value"
         });
 
-        let (bound_sql, bindings) = composer.compose(&stmt.item).expect("compose should work");
-        let (mut mock_bound_sql, mock_bindings) = composer.mock_compose(&mock_values, 0);
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+        let (mut mock_bound_sql, mock_bindings) = ComposerContext::new(&composer, &bind_values, &HashMap::new())
+            .mock_compose(&mock_values, 0)
+            .expect("mock_compose should work");
 
         mock_bound_sql.push(';');
 
@@ -414,9 +551,9 @@ mod tests {
 
         let stmt = SqlComposition::from_path_name("src/tests/values/double-include.tql").unwrap();
 
-        let mut composer = RusqliteComposer::new();
+        let composer = RusqliteComposer::new();
 
-        composer.values = bind_values!(&dyn ToSql:
+        let bind_values = bind_values!(&dyn ToSql:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
@@ -444,8 +581,10 @@ mod tests {
             "col_4" => &"d_value"
             });
 
-        let (bound_sql, bindings) = composer.compose(&stmt.item).expect("compose should work");
-        let (mut mock_bound_sql, _mock_bindings) = composer.mock_compose(&mock_values, 0);
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+        let (mut mock_bound_sql, _mock_bindings) = ComposerContext::new(&composer, &bind_values, &HashMap::new())
+            .mock_compose(&mock_values, 0)
+            .expect("mock_compose should work");
 
         mock_bound_sql.push(';');
 
@@ -510,9 +649,9 @@ mod tests {
             vec!["a_value", "b_value", "c_value", "d_value"],
         ];
 
-        let mut composer = RusqliteComposer::new();
+        let composer = RusqliteComposer::new();
 
-        composer.values = bind_values!(&dyn ToSql:
+        let bind_values = bind_values!(&dyn ToSql:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
@@ -523,7 +662,7 @@ mod tests {
         "col_3_values" => [&"b_value", &"c_value"]
         );
 
-        let (bound_sql, bindings) = composer.compose(&stmt.item).expect("compose should work");
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
 
         let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
 
@@ -563,9 +702,9 @@ mod tests {
 
         let expected_bound_sql = "SELECT COUNT(1) FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 UNION ALL SELECT ?9 AS col_1, ?10 AS col_2, ?11 AS col_3, ?12 AS col_4 ) AS count_main";
 
-        let mut composer = RusqliteComposer::new();
+        let composer = RusqliteComposer::new();
 
-        composer.values = bind_values!(&dyn ToSql:
+        let bind_values = bind_values!(&dyn ToSql:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
@@ -576,7 +715,7 @@ mod tests {
         "col_3_values" => [&"b_value", &"c_value"]
         );
 
-        let (bound_sql, bindings) = composer.compose(&stmt.item).expect("compose should work");
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
 
         assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
 
@@ -605,9 +744,9 @@ mod tests {
 
         let expected_bound_sql = "SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 UNION ALL SELECT ?9 AS col_1, ?10 AS col_2, ?11 AS col_3, ?12 AS col_4 UNION SELECT ?13 AS col_1, ?14 AS col_2, ?15 AS col_3, ?16 AS col_4 UNION ALL SELECT ?17 AS col_1, ?18 AS col_2, ?19 AS col_3, ?20 AS col_4 UNION SELECT ?21 AS col_1, ?22 AS col_2, ?23 AS col_3, ?24 AS col_4 UNION ALL SELECT ?25 AS col_1, ?26 AS col_2, ?27 AS col_3, ?28 AS col_4 UNION ALL SELECT ?29 AS col_1, ?30 AS col_2, ?31 AS col_3, ?32 AS col_4";
 
-        let mut composer = RusqliteComposer::new();
+        let composer = RusqliteComposer::new();
 
-        composer.values = bind_values!(&dyn ToSql:
+        let bind_values = bind_values!(&dyn ToSql:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
@@ -618,7 +757,7 @@ mod tests {
         "col_3_values" => [&"b_value", &"c_value"]
         );
 
-        let (bound_sql, bindings) = composer.compose(&stmt.item).expect("compose should work");
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
 
         assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
 
@@ -656,41 +795,26 @@ mod tests {
     }
 
     #[test]
-    fn test_include_mock_multi_value_bind() {
+    fn test_with_command() {
         let conn = setup_db();
 
-        let (_remaining, stmt) = parse_template(Span::new("SELECT * FROM (:compose(src/tests/values/double-include.tql)) AS main WHERE col_1 in (:bind(col_1_values EXPECTING MIN 1)) AND col_3 IN (:bind(col_3_values EXPECTING MIN 1));".into()), None).unwrap();
-
-        let expected_bound_sql = "SELECT * FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 ) AS main WHERE col_1 in ( ?9, ?10 ) AND col_3 IN ( ?11, ?12 );";
+        let (_remaining, stmt) = parse_template(Span::new(":with(simple_values of src/tests/values/simple.tql, include_values of src/tests/values/include.tql) SELECT i.col_1, i.col_2, i.col_3, i.col_4 FROM include_values AS i JOIN simple_values AS s ON s.col_1 = i.col_1 WHERE i.col_4 IN (SELECT col_4 FROM simple_values AS ss);".into()), None).unwrap();
 
-        let expected_values = vec![
-            vec!["d_value", "f_value", "b_value", "a_value"],
-            vec!["ee_value", "dd_value", "bb_value", "aa_value"],
-        ];
+        let expected_bound_sql = "WITH simple_values AS ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 ), include_values AS ( SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 UNION ALL SELECT ?9 AS col_1, ?10 AS col_2, ?11 AS col_3, ?12 AS col_4 ) SELECT i.col_1, i.col_2, i.col_3, i.col_4 FROM include_values AS i JOIN simple_values AS s ON s.col_1 = i.col_1 WHERE i.col_4 IN (SELECT col_4 FROM simple_values AS ss )";
 
-        let mut composer = RusqliteComposer::new();
+        let composer = RusqliteComposer::new();
 
-        composer.values = bind_values!(&dyn ToSql:
+        let bind_values = bind_values!(&dyn ToSql:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
         "d" => [&"d_value"],
-        "e" => [&"e_value"],
-        "f" => [&"f_value"],
-        "col_1_values" => [&"ee_value", &"d_value"],
-        "col_3_values" => [&"bb_value", &"b_value"]
+        "e" => [&"e_value"]
         );
 
-        composer.mock_values = mock_path_values!(&dyn ToSql: "src/tests/values/include.tql" => [{
-            "col_1" => &"ee_value",
-            "col_2" => &"dd_value",
-            "col_3" => &"bb_value",
-            "col_4" => &"aa_value"
-        }]);
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
 
-        let (bound_sql, bindings) = composer
-            .compose_statement(&stmt, 1, false)
-            .expect("compose_statement should work");
+        assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
 
         let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
 
@@ -714,59 +838,32 @@ mod tests {
             values.push(row.unwrap());
         }
 
-        assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
+        let expected_values = vec![vec!["a_value", "b_value", "c_value", "d_value"]];
+
         assert_eq!(values, expected_values, "exected values");
     }
 
     #[test]
-    fn test_mock_double_include_multi_value_bind() {
+    fn test_exists_command() {
         let conn = setup_db();
 
-        let (_remaining, stmt) = parse_template(Span::new("SELECT * FROM (:compose(src/tests/values/double-include.tql)) AS main WHERE col_1 in (:bind(col_1_values EXPECTING MIN 1)) AND col_3 IN (:bind(col_3_values EXPECTING MIN 1));".into()), None).unwrap();
-
-        let expected_bound_sql = "SELECT * FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 UNION ALL SELECT ?9 AS col_1, ?10 AS col_2, ?11 AS col_3, ?12 AS col_4 ) AS main WHERE col_1 in ( ?13, ?14 ) AND col_3 IN ( ?15, ?16 );";
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1, col_2, col_3, col_4 FROM (:compose(src/tests/values/include.tql)) AS main WHERE :exists(src/tests/values/simple.tql) AND col_1 = :bind(e);".into()), None).unwrap();
 
-        let expected_values = vec![
-            vec!["dd_value", "ff_value", "bb_value", "aa_value"],
-            vec!["dd_value", "ff_value", "bb_value", "aa_value"],
-            vec!["aa_value", "bb_value", "cc_value", "dd_value"],
-        ];
+        let expected_bound_sql = "SELECT col_1, col_2, col_3, col_4 FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 ) AS main WHERE EXISTS (SELECT 1 FROM ( SELECT ?9 AS col_1, ?10 AS col_2, ?11 AS col_3, ?12 AS col_4 ) AS exists_main) AND col_1 = ?13;";
 
-        let mut composer = RusqliteComposer::new();
+        let composer = RusqliteComposer::new();
 
-        composer.values = bind_values!(&dyn ToSql:
+        let bind_values = bind_values!(&dyn ToSql:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
         "d" => [&"d_value"],
-        "e" => [&"e_value"],
-        "f" => [&"f_value"],
-        "col_1_values" => [&"dd_value", &"aa_value"],
-        "col_3_values" => [&"bb_value", &"cc_value"]
+        "e" => [&"e_value"]
         );
 
-        composer.mock_values = mock_path_values!(&dyn ToSql: "src/tests/values/double-include.tql" => [{
-            "col_1" => &"dd_value",
-            "col_2" => &"ff_value",
-            "col_3" => &"bb_value",
-            "col_4" => &"aa_value"
-            },
-            {
-            "col_1" => &"dd_value",
-            "col_2" => &"ff_value",
-            "col_3" => &"bb_value",
-            "col_4" => &"aa_value"
-            },
-            {
-            "col_1" => &"aa_value",
-            "col_2" => &"bb_value",
-            "col_3" => &"cc_value",
-            "col_4" => &"dd_value"
-        }]);
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
 
-        let (bound_sql, bindings) = composer
-            .compose_statement(&stmt, 1, false)
-            .expect("compose_statement should work");
+        assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
 
         let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
 
@@ -790,110 +887,1380 @@ mod tests {
             values.push(row.unwrap());
         }
 
-        assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
+        let expected_values = vec![vec!["e_value", "d_value", "b_value", "a_value"]];
+
         assert_eq!(values, expected_values, "exected values");
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1, col_2, col_3, col_4 FROM (:compose(src/tests/values/include.tql)) AS main WHERE :not_exists(src/tests/values/simple.tql);".into()), None).unwrap();
+
+        let expected_bound_sql = "SELECT col_1, col_2, col_3, col_4 FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 ) AS main WHERE NOT EXISTS (SELECT 1 FROM ( SELECT ?9 AS col_1, ?10 AS col_2, ?11 AS col_3, ?12 AS col_4 ) AS exists_main);";
+
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+        assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
+
+        let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+        let count = prep_stmt
+            .query_map(&bindings, |_row| Ok(()))
+            .unwrap()
+            .count();
+
+        assert_eq!(count, 0, "no rows when the composition has rows");
     }
 
     #[test]
-    fn test_mock_db_object() {
+    fn test_conditional() {
         let conn = setup_db();
 
-        let (_remaining, stmt) = parse_template(Span::new("SELECT * FROM main WHERE col_1 in (:bind(col_1_values EXPECTING MIN 1)) AND col_3 IN (:bind(col_3_values EXPECTING MIN 1));".into()), None).unwrap();
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1, col_2, col_3, col_4 FROM (:compose(src/tests/values/include.tql)) AS main WHERE col_4 <> :bind(c) :if(f) AND col_1 = :bind(f) :else AND col_1 = :bind(a) :endif;".into()), None).unwrap();
 
-        let expected_bound_sql = "SELECT * FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 UNION ALL SELECT ?9 AS col_1, ?10 AS col_2, ?11 AS col_3, ?12 AS col_4 ) AS main WHERE col_1 in ( ?13, ?14 ) AND col_3 IN ( ?15, ?16 );";
+        let composer = RusqliteComposer::new();
 
-        let expected_values = vec![
-            vec!["dd_value", "ff_value", "bb_value", "aa_value"],
-            vec!["dd_value", "ff_value", "bb_value", "aa_value"],
-            vec!["aa_value", "bb_value", "cc_value", "dd_value"],
+        let mut bind_values = bind_values!(&dyn ToSql:
+        "a" => [&"a_value"],
+        "b" => [&"b_value"],
+        "c" => [&"c_value"],
+        "d" => [&"d_value"],
+        "e" => [&"e_value"]
+        );
+
+        let tests = vec![
+            (
+                None,
+                "SELECT col_1, col_2, col_3, col_4 FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 ) AS main WHERE col_4 <> ?9 AND col_1 = ?10;",
+                vec![vec!["a_value", "b_value", "c_value", "d_value"]],
+            ),
+            (
+                Some(vec![]),
+                "SELECT col_1, col_2, col_3, col_4 FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 ) AS main WHERE col_4 <> ?9 AND col_1 = ?10;",
+                vec![vec!["a_value", "b_value", "c_value", "d_value"]],
+            ),
+            (
+                Some(vec![&"e_value" as &dyn ToSql]),
+                "SELECT col_1, col_2, col_3, col_4 FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 ) AS main WHERE col_4 <> ?9 AND col_1 = ?10;",
+                vec![vec!["e_value", "d_value", "b_value", "a_value"]],
+            ),
         ];
 
-        let mut composer = RusqliteComposer::new();
+        for (f, expected_bound_sql, expected_values) in tests {
+            match f {
+                Some(f) => {
+                    bind_values.insert("f".into(), f);
+                }
+                None => {
+                    bind_values.remove("f");
+                }
+            }
+
+            let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+            assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
+            assert_eq!(bindings.len(), 10, "excluded branch values are skipped");
+
+            let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+            let mut values: Vec<Vec<String>> = vec![];
+
+            let rows = prep_stmt
+                .query_map(&bindings, |row| {
+                    (0..4).fold(Ok(Vec::new()), |acc, i| {
+                        if let Ok(mut acc) = acc {
+                            acc.push(row.get(i).unwrap());
+                            Ok(acc)
+                        }
+                        else {
+                            acc
+                        }
+                    })
+                })
+                .unwrap();
+
+            for row in rows {
+                values.push(row.unwrap());
+            }
+
+            assert_eq!(values, expected_values, "exected values");
+        }
+    }
+
+    #[test]
+    fn test_predicates() {
+        let conn = setup_db();
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1, col_2, col_3, col_4 FROM (:compose(src/tests/values/include.tql)) AS main WHERE :and(col_1 = :bind(f)) :or(col_2 IN (:bind(g EXPECTING MIN 1))) :and(col_4 = :bind(h EXPECTING 1));".into()), None).unwrap();
+
+        let select = "SELECT col_1, col_2, col_3, col_4 FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 ) AS main";
+
+        let row_1 = vec!["e_value", "d_value", "b_value", "a_value"];
+        let row_2 = vec!["a_value", "b_value", "c_value", "d_value"];
+
+        let tests: Vec<(CaseValues, String, CaseRows)> = vec![
+            (
+                vec![("f", vec![&"e_value"])],
+                format!("{} WHERE col_1 = ?9;", select),
+                vec![row_1.clone()],
+            ),
+            (
+                vec![("g", vec![&"b_value", &"d_value"])],
+                format!("{} WHERE col_2 IN ( ?9, ?10 );", select),
+                vec![row_1.clone(), row_2.clone()],
+            ),
+            (
+                vec![("f", vec![&"e_value"]), ("h", vec![&"a_value"])],
+                format!("{} WHERE col_1 = ?9 AND col_4 = ?10;", select),
+                vec![row_1.clone()],
+            ),
+            (
+                vec![("f", vec![&"c_value"]), ("g", vec![&"b_value"])],
+                format!("{} WHERE col_1 = ?9 OR col_2 IN ( ?10 );", select),
+                vec![row_2.clone()],
+            ),
+            (
+                vec![("f", vec![&"c_value"]), ("g", vec![&"b_value"]), ("h", vec![&"d_value"])],
+                format!("{} WHERE (col_1 = ?9 OR col_2 IN ( ?10 )) AND col_4 = ?11;", select),
+                vec![row_2.clone()],
+            ),
+            (
+                vec![],
+                format!("{};", select),
+                vec![row_1.clone(), row_2.clone()],
+            ),
+        ];
+
+        for (filters, expected_bound_sql, expected_values) in tests {
+            let composer = RusqliteComposer::new();
+
+            let mut bind_values = bind_values!(&dyn ToSql:
+            "a" => [&"a_value"],
+            "b" => [&"b_value"],
+            "c" => [&"c_value"],
+            "d" => [&"d_value"],
+            "e" => [&"e_value"]
+            );
+
+            for (name, values) in filters {
+                bind_values.insert(name.into(), values);
+            }
+
+            let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+            assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
+
+            let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+            let mut values: Vec<Vec<String>> = vec![];
+
+            let rows = prep_stmt
+                .query_map(&bindings, |row| {
+                    (0..4).fold(Ok(Vec::new()), |acc, i| {
+                        if let Ok(mut acc) = acc {
+                            acc.push(row.get(i).unwrap());
+                            Ok(acc)
+                        }
+                        else {
+                            acc
+                        }
+                    })
+                })
+                .unwrap();
+
+            for row in rows {
+                values.push(row.unwrap());
+            }
 
-        composer.values = bind_values!(&dyn ToSql:
+            assert_eq!(values, expected_values, "exected values");
+        }
+
+        let composer = RusqliteComposer::new();
+
+        let bind_values = bind_values!(&dyn ToSql:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
         "d" => [&"d_value"],
         "e" => [&"e_value"],
-        "f" => [&"f_value"],
-        "col_1_values" => [&"dd_value", &"aa_value"],
-        "col_3_values" => [&"bb_value", &"cc_value"]
+        "h" => [&"a_value", &"d_value"]
         );
 
-        composer.mock_values = mock_db_object_values!(&dyn ToSql: "main" => [{
-            "col_1" => &"dd_value",
-            "col_2" => &"ff_value",
-            "col_3" => &"bb_value",
-            "col_4" => &"aa_value"
-        },
-            {
-            "col_1" => &"dd_value",
-            "col_2" => &"ff_value",
-            "col_3" => &"bb_value",
-            "col_4" => &"aa_value"
-        },
-            {
-            "col_1" => &"aa_value",
-            "col_2" => &"bb_value",
-            "col_3" => &"cc_value",
-            "col_4" => &"dd_value"
-        }]);
+        assert!(
+            composer.compose(&stmt.item, &bind_values, &HashMap::new()).is_err(),
+            "bound predicates keep their expected value counts"
+        );
 
-        let (bound_sql, bindings) = composer
-            .compose_statement(&stmt, 1, false)
-            .expect("compose_statement should work");
+        let tests: Vec<(&str, CaseValues, String, CaseRows)> = vec![
+            (
+                "WHERE :and(col_1 = :bind(x)) AND col_4 = :bind(h)",
+                vec![("h", vec![&"a_value"])],
+                format!("{} WHERE col_4 = ?9;", select),
+                vec![row_1.clone()],
+            ),
+            (
+                "WHERE :and(col_1 = :bind(x)) AND col_4 = :bind(h)",
+                vec![("x", vec![&"e_value"]), ("h", vec![&"a_value"])],
+                format!("{} WHERE col_1 = ?9 AND col_4 = ?10;", select),
+                vec![row_1.clone()],
+            ),
+            (
+                "WHERE :if(y) :and(col_1 = :bind(y)) :endif",
+                vec![("y", vec![&"a_value"])],
+                format!("{} WHERE col_1 = ?9;", select),
+                vec![row_2.clone()],
+            ),
+            (
+                "WHERE :if(y) :and(col_1 = :bind(y)) :endif",
+                vec![],
+                format!("{};", select),
+                vec![row_1.clone(), row_2.clone()],
+            ),
+        ];
 
-        let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
+        for (filter, filters, expected_bound_sql, expected_values) in tests {
+            let (_remaining, stmt) = parse_template(Span::new(&format!("SELECT col_1, col_2, col_3, col_4 FROM (:compose(src/tests/values/include.tql)) AS main {};", filter)), None).unwrap();
 
-        let mut values: Vec<Vec<String>> = vec![];
+            let mut bind_values = bind_values!(&dyn ToSql:
+            "a" => [&"a_value"],
+            "b" => [&"b_value"],
+            "c" => [&"c_value"],
+            "d" => [&"d_value"],
+            "e" => [&"e_value"]
+            );
 
-        let rows = prep_stmt
-            .query_map(&bindings, |row| {
-                (0..4).fold(Ok(Vec::new()), |acc, i| {
-                    if let Ok(mut acc) = acc {
-                        acc.push(row.get(i).unwrap());
-                        Ok(acc)
-                    }
-                    else {
-                        acc
-                    }
+            for (name, values) in filters {
+                bind_values.insert(name.into(), values);
+            }
+
+            let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+            assert_eq!(bound_sql, expected_bound_sql, "predicates left out ahead of a condition");
+
+            let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+            let values: Vec<Vec<String>> = prep_stmt
+                .query_map(&bindings, |row| {
+                    (0..4).map(|i| row.get(i)).collect::<Result<Vec<String>, _>>()
                 })
-            })
-            .unwrap();
+                .unwrap()
+                .map(|row| row.unwrap())
+                .collect();
 
-        for row in rows {
-            values.push(row.unwrap());
+            assert_eq!(values, expected_values, "exected values");
         }
+    }
 
-        assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
-        assert_eq!(values, expected_values, "exected values");
+    #[test]
+    fn test_predicates_with_or() {
+        let conn = setup_db();
+
+        let select = "SELECT col_1, col_2, col_3, col_4 FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 ) AS main";
+
+        let tests = vec![
+            (
+                "WHERE col_1 = :bind(f) OR col_1 = :bind(g) :and(col_4 = :bind(h))",
+                format!("{} WHERE (col_1 = ?9 OR col_1 = ?10) AND col_4 = ?11;", select),
+                vec![vec!["a_value", "b_value", "c_value", "d_value"]],
+            ),
+            (
+                "WHERE col_1 = :bind(f) :and(col_4 = :bind(h) OR col_4 = :bind(i))",
+                format!("{} WHERE col_1 = ?9 AND (col_4 = ?10 OR col_4 = ?11);", select),
+                vec![vec!["e_value", "d_value", "b_value", "a_value"]],
+            ),
+        ];
+
+        for (filter, expected_bound_sql, expected_values) in tests {
+            let (_remaining, stmt) = parse_template(Span::new(&format!("SELECT col_1, col_2, col_3, col_4 FROM (:compose(src/tests/values/include.tql)) AS main {};", filter)), None).unwrap();
+
+            let composer = RusqliteComposer::new();
+
+            let bind_values = bind_values!(&dyn ToSql:
+            "a" => [&"a_value"],
+            "b" => [&"b_value"],
+            "c" => [&"c_value"],
+            "d" => [&"d_value"],
+            "e" => [&"e_value"],
+            "f" => [&"e_value"],
+            "g" => [&"a_value"],
+            "h" => [&"d_value"],
+            "i" => [&"a_value"]
+            );
+
+            let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+            assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
+
+            let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+            let values: Vec<Vec<String>> = prep_stmt
+                .query_map(&bindings, |row| {
+                    (0..4).map(|i| row.get(i)).collect::<Result<Vec<String>, _>>()
+                })
+                .unwrap()
+                .map(|row| row.unwrap())
+                .collect();
+
+            assert_eq!(values, expected_values, "conditions before the predicate are grouped");
+        }
     }
 
     #[test]
-    fn it_composes_from_connection() {
+    fn test_identifier() {
         let conn = setup_db();
 
-        let stmt = SqlComposition::from_path_name("src/tests/values/simple.tql".into()).unwrap();
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1, col_2 FROM (:compose(src/tests/values/include.tql)) AS main ORDER BY :ident(sort ALLOW col_1, main.col_2) DESC;".into()), None).unwrap();
 
-        // TODO: why isn't composer used?
-        let _composer = RusqliteComposer::new();
+        let composer = RusqliteComposer::new();
 
-        let bind_values = bind_values!(&dyn ToSql:
+        let mut bind_values = bind_values!(&dyn ToSql:
         "a" => [&"a_value"],
         "b" => [&"b_value"],
         "c" => [&"c_value"],
-        "d" => [&"d_value"]
+        "d" => [&"d_value"],
+        "e" => [&"e_value"],
+        "sort" => [&"main.col_2"]
         );
 
-        let (mut prep_stmt, bindings) = conn
-            .compose(&stmt.item, bind_values, vec![], HashMap::new())
-            .unwrap();
+        let expected_bound_sql = "SELECT col_1, col_2 FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 ) AS main ORDER BY \"main\".\"col_2\" DESC;";
+
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+        assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
+        assert_eq!(bindings.len(), 8, "identifiers are not bound as values");
+
+        let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
 
         let mut values: Vec<Vec<String>> = vec![];
 
         let rows = prep_stmt
-            .query_map(&bindings, |row| {
+            .query_map(&bindings, |row| Ok(vec![row.get(0).unwrap(), row.get(1).unwrap()]))
+            .unwrap();
+
+        for row in rows {
+            values.push(row.unwrap());
+        }
+
+        let expected_values = vec![vec!["e_value", "d_value"], vec!["a_value", "b_value"]];
+
+        assert_eq!(values, expected_values, "exected values");
+
+        for sort in [&"col_2", &"col_1; DROP TABLE main", &"\"col_1\""] {
+            bind_values.insert("sort".into(), vec![sort]);
+
+            assert!(
+                composer.compose(&stmt.item, &bind_values, &HashMap::new()).is_err(),
+                "identifiers outside the allow list are rejected"
+            );
+        }
+
+        bind_values.insert("sort".into(), vec![&1]);
+
+        assert!(
+            composer.compose(&stmt.item, &bind_values, &HashMap::new()).is_err(),
+            "identifiers must be text"
+        );
+    }
+
+    #[test]
+    fn test_order_by() {
+        let conn = setup_db();
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1, col_2 FROM (:compose(src/tests/values/double-include.tql)) AS main :order_by(sort ALLOW col_1, col_2 DEFAULT col_2 DESC);".into()), None).unwrap();
+
+        let select = "SELECT col_1, col_2 FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 UNION ALL SELECT ?9 AS col_1, ?10 AS col_2, ?11 AS col_3, ?12 AS col_4 ) AS main";
+
+        let tests: Vec<(CaseValue, String, CaseRows)> = vec![
+            (
+                None,
+                format!("{} ORDER BY \"col_2\" DESC;", select),
+                vec![
+                    vec!["d_value", "f_value"],
+                    vec!["e_value", "d_value"],
+                    vec!["a_value", "b_value"],
+                ],
+            ),
+            (
+                Some(vec![&"col_1"]),
+                format!("{} ORDER BY \"col_1\";", select),
+                vec![
+                    vec!["a_value", "b_value"],
+                    vec!["d_value", "f_value"],
+                    vec!["e_value", "d_value"],
+                ],
+            ),
+            (
+                Some(vec![&"col_2 asc", &"col_1 DESC"]),
+                format!("{} ORDER BY \"col_2\" ASC, \"col_1\" DESC;", select),
+                vec![
+                    vec!["a_value", "b_value"],
+                    vec!["e_value", "d_value"],
+                    vec!["d_value", "f_value"],
+                ],
+            ),
+        ];
+
+        for (sort, expected_bound_sql, expected_values) in tests {
+            let composer = RusqliteComposer::new();
+
+            let mut bind_values = bind_values!(&dyn ToSql:
+            "a" => [&"a_value"],
+            "b" => [&"b_value"],
+            "c" => [&"c_value"],
+            "d" => [&"d_value"],
+            "e" => [&"e_value"],
+            "f" => [&"f_value"]
+            );
+
+            if let Some(sort) = sort {
+                bind_values.insert("sort".into(), sort);
+            }
+
+            let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+            assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
+
+            let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+            let mut values: Vec<Vec<String>> = vec![];
+
+            let rows = prep_stmt
+                .query_map(&bindings, |row| Ok(vec![row.get(0).unwrap(), row.get(1).unwrap()]))
+                .unwrap();
+
+            for row in rows {
+                values.push(row.unwrap());
+            }
+
+            assert_eq!(values, expected_values, "exected values");
+        }
+
+        let bad_sorts: Vec<&dyn ToSql> = vec![&"col_3", &"col_1 SIDEWAYS", &"col_1 DESC, col_3", &"col_1 DESC NULLS"];
+
+        for bad_sort in bad_sorts {
+            let composer = RusqliteComposer::new();
+
+            let bind_values = bind_values!(&dyn ToSql:
+            "a" => [&"a_value"],
+            "b" => [&"b_value"],
+            "c" => [&"c_value"],
+            "d" => [&"d_value"],
+            "e" => [&"e_value"],
+            "f" => [&"f_value"],
+            "sort" => [bad_sort]
+            );
+
+            assert!(
+                composer.compose(&stmt.item, &bind_values, &HashMap::new()).is_err(),
+                "sort terms are validated"
+            );
+        }
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1, col_2 FROM (:compose(src/tests/values/simple.tql)) AS main :order_by(sort ALLOW col_1);".into()), None).unwrap();
+
+        let composer = RusqliteComposer::new();
+
+        let bind_values = bind_values!(&dyn ToSql:
+        "a" => [&"a_value"],
+        "b" => [&"b_value"],
+        "c" => [&"c_value"],
+        "d" => [&"d_value"]
+        );
+
+        let (bound_sql, _bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+        assert_eq!(
+            bound_sql,
+            "SELECT col_1, col_2 FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 ) AS main;",
+            "no ORDER BY without a sort or default"
+        );
+    }
+
+    #[test]
+    fn test_paginate() {
+        let conn = setup_db();
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1, col_2 FROM (:compose(src/tests/values/double-include.tql)) AS main ORDER BY col_1 :paginate(limit, offset);".into()), None).unwrap();
+
+        let select = "SELECT col_1, col_2 FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 UNION ALL SELECT ?9 AS col_1, ?10 AS col_2, ?11 AS col_3, ?12 AS col_4 ) AS main ORDER BY col_1";
+
+        let tests: Vec<(CaseValues, String, CaseRows)> = vec![
+            (
+                vec![("limit", vec![&2]), ("offset", vec![&1])],
+                format!("{} LIMIT ?13 OFFSET ?14;", select),
+                vec![vec!["d_value", "f_value"], vec!["e_value", "d_value"]],
+            ),
+            (
+                vec![("limit", vec![&1])],
+                format!("{} LIMIT ?13;", select),
+                vec![vec!["a_value", "b_value"]],
+            ),
+            (
+                vec![("offset", vec![&2])],
+                format!("{} LIMIT -1 OFFSET ?13;", select),
+                vec![vec!["e_value", "d_value"]],
+            ),
+            (
+                vec![],
+                format!("{};", select),
+                vec![
+                    vec!["a_value", "b_value"],
+                    vec!["d_value", "f_value"],
+                    vec!["e_value", "d_value"],
+                ],
+            ),
+        ];
+
+        for (page, expected_bound_sql, expected_values) in tests {
+            let composer = RusqliteComposer::new();
+
+            let mut bind_values = bind_values!(&dyn ToSql:
+            "a" => [&"a_value"],
+            "b" => [&"b_value"],
+            "c" => [&"c_value"],
+            "d" => [&"d_value"],
+            "e" => [&"e_value"],
+            "f" => [&"f_value"]
+            );
+
+            for (name, values) in page {
+                bind_values.insert(name.into(), values);
+            }
+
+            let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+            assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
+
+            let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+            let mut values: Vec<Vec<String>> = vec![];
+
+            let rows = prep_stmt
+                .query_map(&bindings, |row| Ok(vec![row.get(0).unwrap(), row.get(1).unwrap()]))
+                .unwrap();
+
+            for row in rows {
+                values.push(row.unwrap());
+            }
+
+            assert_eq!(values, expected_values, "exected values");
+        }
+
+        let composer = RusqliteComposer::new();
+
+        let bind_values = bind_values!(&dyn ToSql:
+        "a" => [&"a_value"],
+        "b" => [&"b_value"],
+        "c" => [&"c_value"],
+        "d" => [&"d_value"],
+        "e" => [&"e_value"],
+        "f" => [&"f_value"],
+        "limit" => [&1, &2]
+        );
+
+        assert!(
+            composer.compose(&stmt.item, &bind_values, &HashMap::new()).is_err(),
+            "limit takes a single value"
+        );
+    }
+
+    #[test]
+    fn test_paginate_after() {
+        let conn = setup_db();
+
+        let select = "SELECT col_1, col_2 FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 UNION ALL SELECT ?9 AS col_1, ?10 AS col_2, ?11 AS col_3, ?12 AS col_4 ) AS main";
+
+        let tests: Vec<(&str, CaseValues, String, CaseRows)> = vec![
+            (
+                "",
+                vec![("after", vec![&"a_value"]), ("limit", vec![&1])],
+                format!("{} WHERE \"col_1\" > ?13 ORDER BY \"col_1\" LIMIT ?14;", select),
+                vec![vec!["d_value", "f_value"]],
+            ),
+            (
+                "",
+                vec![("limit", vec![&1])],
+                format!("{} ORDER BY \"col_1\" LIMIT ?13;", select),
+                vec![vec!["a_value", "b_value"]],
+            ),
+            (
+                "WHERE col_2 <> :bind(f)",
+                vec![("after", vec![&"a_value"])],
+                format!("{} WHERE col_2 <> ?13 AND \"col_1\" > ?14 ORDER BY \"col_1\";", select),
+                vec![vec!["e_value", "d_value"]],
+            ),
+            (
+                "WHERE col_2 = :bind(b) OR col_2 = :bind(d)",
+                vec![("after", vec![&"a_value"])],
+                format!("{} WHERE (col_2 = ?13 OR col_2 = ?14) AND \"col_1\" > ?15 ORDER BY \"col_1\";", select),
+                vec![vec!["e_value", "d_value"]],
+            ),
+            (
+                "WHERE",
+                vec![("after", vec![&"d_value"])],
+                format!("{} WHERE \"col_1\" > ?13 ORDER BY \"col_1\";", select),
+                vec![vec!["e_value", "d_value"]],
+            ),
+            (
+                "WHERE",
+                vec![],
+                format!("{} ORDER BY \"col_1\";", select),
+                vec![
+                    vec!["a_value", "b_value"],
+                    vec!["d_value", "f_value"],
+                    vec!["e_value", "d_value"],
+                ],
+            ),
+        ];
+
+        for (filter, page, expected_bound_sql, expected_values) in tests {
+            let (_remaining, stmt) = parse_template(Span::new(&format!("SELECT col_1, col_2 FROM (:compose(src/tests/values/double-include.tql)) AS main {} :paginate_after(col_1, after, limit);", filter)), None).unwrap();
+
+            let composer = RusqliteComposer::new();
+
+            let mut bind_values = bind_values!(&dyn ToSql:
+            "a" => [&"a_value"],
+            "b" => [&"b_value"],
+            "c" => [&"c_value"],
+            "d" => [&"d_value"],
+            "e" => [&"e_value"],
+            "f" => [&"f_value"]
+            );
+
+            for (name, values) in page {
+                bind_values.insert(name.into(), values);
+            }
+
+            let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+            assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
+
+            let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+            let mut values: Vec<Vec<String>> = vec![];
+
+            let rows = prep_stmt
+                .query_map(&bindings, |row| Ok(vec![row.get(0).unwrap(), row.get(1).unwrap()]))
+                .unwrap();
+
+            for row in rows {
+                values.push(row.unwrap());
+            }
+
+            assert_eq!(values, expected_values, "exected values");
+        }
+    }
+
+    #[test]
+    fn test_compose_page() {
+        let conn = setup_db();
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1, col_2 FROM (:compose(src/tests/values/double-include.tql)) AS main WHERE col_2 <> :bind(c) ORDER BY col_1;".into()), None).unwrap();
+
+        let composer = RusqliteComposer::new();
+
+        let bind_values = bind_values!(&dyn ToSql:
+        "a" => [&"a_value"],
+        "b" => [&"b_value"],
+        "c" => [&"c_value"],
+        "d" => [&"d_value"],
+        "e" => [&"e_value"],
+        "f" => [&"f_value"],
+        "limit" => [&2],
+        "offset" => [&1]
+        );
+
+        let page = SqlPagination::new("limit".into(), Some("offset".into())).unwrap();
+
+        let ((page_sql, page_bindings), (count_sql, count_bindings)) = composer
+            .compose_page(&stmt.item, &page, &bind_values, &HashMap::new())
+            .expect("compose_page should work");
+
+        let select = "SELECT col_1, col_2 FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 UNION ALL SELECT ?9 AS col_1, ?10 AS col_2, ?11 AS col_3, ?12 AS col_4 ) AS main WHERE col_2 <> ?13";
+
+        assert_eq!(
+            page_sql,
+            format!("{} ORDER BY col_1 LIMIT ?14 OFFSET ?15;", select),
+            "page statement"
+        );
+        assert_eq!(page_bindings.len(), 15, "page values");
+
+        assert_eq!(
+            count_sql,
+            format!("SELECT COUNT(1) FROM ( {} ) AS count_main;", select),
+            "count statement"
+        );
+        assert_eq!(count_bindings.len(), 13, "count values");
+
+        let mut prep_stmt = conn.prepare(&page_sql).unwrap();
+
+        let mut values: Vec<Vec<String>> = vec![];
+
+        let rows = prep_stmt
+            .query_map(&page_bindings, |row| Ok(vec![row.get(0).unwrap(), row.get(1).unwrap()]))
+            .unwrap();
+
+        for row in rows {
+            values.push(row.unwrap());
+        }
+
+        assert_eq!(
+            values,
+            vec![vec!["d_value", "f_value"], vec!["e_value", "d_value"]],
+            "page values"
+        );
+
+        let mut prep_stmt = conn.prepare(&count_sql).unwrap();
+
+        let total: i64 = prep_stmt
+            .query_row(&count_bindings, |row| row.get(0))
+            .unwrap();
+
+        assert_eq!(total, 3, "total");
+    }
+
+    #[test]
+    fn test_bind_rows() {
+        let conn = setup_db();
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1, col_2 FROM (:compose(src/tests/values/double-include.tql)) AS main WHERE (col_1, col_2) IN (VALUES :bind_rows(pairs COLUMNS col_1, col_2 EXPECTING MAX 3)) ORDER BY col_1;".into()), None).unwrap();
+
+        let composer = RusqliteComposer::new();
+
+        let mut bind_values = bind_values!(&dyn ToSql:
+        "a" => [&"a_value"],
+        "b" => [&"b_value"],
+        "c" => [&"c_value"],
+        "d" => [&"d_value"],
+        "e" => [&"e_value"],
+        "f" => [&"f_value"],
+        "pairs" => [&"a_value", &"b_value", &"e_value", &"f_value", &"e_value", &"d_value"]
+        );
+
+        let expected_bound_sql = "SELECT col_1, col_2 FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 UNION ALL SELECT ?9 AS col_1, ?10 AS col_2, ?11 AS col_3, ?12 AS col_4 ) AS main WHERE (col_1, col_2) IN (VALUES (?13, ?14), (?15, ?16), (?17, ?18) ) ORDER BY col_1;";
+
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+        assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
+
+        let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+        let mut values: Vec<Vec<String>> = vec![];
+
+        let rows = prep_stmt
+            .query_map(&bindings, |row| Ok(vec![row.get(0).unwrap(), row.get(1).unwrap()]))
+            .unwrap();
+
+        for row in rows {
+            values.push(row.unwrap());
+        }
+
+        let expected_values = vec![vec!["a_value", "b_value"], vec!["e_value", "d_value"]];
+
+        assert_eq!(values, expected_values, "exected values");
+
+        let bad_pairs: Vec<Vec<&dyn ToSql>> = vec![
+            vec![&"a_value", &"b_value", &"e_value"],
+            vec![&"a", &"b", &"c", &"d", &"e", &"f", &"g", &"h"],
+            vec![],
+        ];
+
+        for pairs in bad_pairs {
+            bind_values.insert("pairs".into(), pairs);
+
+            assert!(
+                composer.compose(&stmt.item, &bind_values, &HashMap::new()).is_err(),
+                "rows must be complete and within the expected count"
+            );
+        }
+    }
+
+    #[test]
+    fn test_values_chunked() {
+        let conn = setup_db();
+
+        let (_remaining, stmt) = parse_template(Span::new("INSERT INTO person (name, time_created) :values(people);".into()), None).unwrap();
+
+        let mut composer = RusqliteComposer::new();
+
+        let expected_max = if rusqlite::version_number() >= 3_032_000 { 32766 } else { 999 };
+
+        assert_eq!(composer.max_bind_params(), expected_max, "limit of the linked SQLite");
+
+        composer.set_max_bind_params(999);
+
+        let mut bind_values = bind_values!(&dyn ToSql:
+        "people" => [&"Steven", &"2019-01-01", &"Kenny", &"2019-01-02"]
+        );
+
+        let statements = composer
+            .compose_chunked(&stmt.item, "people", &bind_values, &HashMap::new())
+            .expect("compose_chunked should work");
+
+        assert_eq!(statements.len(), 1, "fits in one statement");
+        assert_eq!(
+            statements[0].0,
+            "INSERT INTO person (name, time_created) VALUES (?1, ?2), (?3, ?4);",
+            "preparable statements match"
+        );
+        assert_eq!(statements[0].1.len(), 4, "values");
+
+        let row: Vec<&dyn ToSql> = vec![&"Person", &"2019-01-01"];
+
+        let people: Vec<&dyn ToSql> = row.into_iter().cycle().take(1200).collect();
+
+        bind_values.insert("people".into(), people);
+
+        let statements = composer
+            .compose_chunked(&stmt.item, "people", &bind_values, &HashMap::new())
+            .expect("compose_chunked should work");
+
+        assert_eq!(
+            statements.iter().map(|(_, v)| v.len()).collect::<Vec<usize>>(),
+            vec![998, 202],
+            "rows are split to stay within the parameter limit"
+        );
+
+        for (bound_sql, bindings) in &statements {
+            conn.execute(bound_sql, bindings).unwrap();
+        }
+
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM person", NO_PARAMS, |row| row.get(0))
+            .unwrap();
+
+        assert_eq!(count, 600, "every row inserted");
+
+        bind_values.insert("people".into(), vec![&"Steven", &"2019-01-01", &"Kenny"]);
+
+        assert!(
+            composer.compose_chunked(&stmt.item, "people", &bind_values, &HashMap::new()).is_err(),
+            "rows must be complete"
+        );
+    }
+
+    /// Excludes the values bound to `excluded`, which it binds itself when given any.
+    struct ExcludeMacro(Vec<Value>);
+
+    impl MacroHandler for ExcludeMacro {
+        fn expand(
+            &self,
+            composition: &SqlComposition,
+        ) -> Result<(SqlComposition, BTreeMap<String, Vec<Value>>), ()> {
+            let column = match &composition.columns {
+                Some(c) if c.len() == 1 => c[0].item(),
+                _ => return Err(()),
+            };
+
+            let mut out = SqlComposition::default();
+
+            out.push_generated_literal("SELECT * FROM (", None).unwrap();
+
+            for of in &composition.of {
+                out.push_generated_compose(of, &composition.aliases, None)
+                    .unwrap();
+            }
+
+            out.push_generated_literal(
+                &format!(") AS exclude_main WHERE {} NOT IN (", column),
+                None,
+            )
+            .unwrap();
+
+            out.push_generated_binding(
+                SqlBinding::new("excluded".into(), false, Some(1), None, false).unwrap(),
+                None,
+            )
+            .unwrap();
+
+            out.push_generated_literal(")", None).unwrap();
+
+            let mut values = BTreeMap::new();
+
+            if !self.0.is_empty() {
+                values.insert("excluded".to_string(), self.0.clone());
+            }
+
+            Ok((out, values))
+        }
+    }
+
+    #[test]
+    fn test_custom_macro() {
+        let conn = setup_db();
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1, col_2, col_3, col_4 FROM (:exclude(col_1 of src/tests/values/double-include.tql)) AS main WHERE col_4 = :bind(a);".into()), None).unwrap();
+
+        let expected_bound_sql = "SELECT col_1, col_2, col_3, col_4 FROM ( SELECT * FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 UNION ALL SELECT ?9 AS col_1, ?10 AS col_2, ?11 AS col_3, ?12 AS col_4 ) AS exclude_main WHERE col_1 NOT IN ( ?13, ?14 ) ) AS main WHERE col_4 = ?15;";
+
+        let mut composer = RusqliteComposer::new();
+
+        composer.insert_macro("EXCLUDE".into(), Box::new(ExcludeMacro(vec![])));
+
+        let bind_values = bind_values!(&dyn ToSql:
+        "a" => [&"a_value"],
+        "b" => [&"b_value"],
+        "c" => [&"c_value"],
+        "d" => [&"d_value"],
+        "e" => [&"e_value"],
+        "f" => [&"f_value"],
+        "excluded" => [&"e_value", &"f_value"]
+        );
+
+        let (bound_sql, bindings) = composer.compose(&stmt.item, &bind_values, &HashMap::new()).expect("compose should work");
+
+        assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
+
+        let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+        let mut values: Vec<Vec<String>> = vec![];
+
+        let rows = prep_stmt
+            .query_map(&bindings, |row| {
+                (0..4).fold(Ok(Vec::new()), |acc, i| {
+                    if let Ok(mut acc) = acc {
+                        acc.push(row.get(i).unwrap());
+                        Ok(acc)
+                    }
+                    else {
+                        acc
+                    }
+                })
+            })
+            .unwrap();
+
+        for row in rows {
+            values.push(row.unwrap());
+        }
+
+        let expected_values = vec![vec!["d_value", "f_value", "b_value", "a_value"]];
+
+        assert_eq!(values, expected_values, "exected values");
+    }
+
+    #[test]
+    fn test_custom_macro_values() {
+        let conn = setup_db();
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1, col_2, col_3, col_4 FROM (:exclude(col_1 of src/tests/values/double-include.tql)) AS main WHERE col_4 = :bind(a);".into()), None).unwrap();
+
+        let expected_bound_sql = "SELECT col_1, col_2, col_3, col_4 FROM ( SELECT * FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 UNION ALL SELECT ?9 AS col_1, ?10 AS col_2, ?11 AS col_3, ?12 AS col_4 ) AS exclude_main WHERE col_1 NOT IN ( ?13, ?14 ) ) AS main WHERE col_4 = ?15;";
+
+        let mut composer = RusqliteComposer::new();
+
+        composer.insert_macro(
+            "EXCLUDE".into(),
+            Box::new(ExcludeMacro(vec![
+                Value::Text("e_value".into()),
+                Value::Text("f_value".into()),
+            ])),
+        );
+
+        let bind_values = bind_values!(&dyn ToSql:
+        "a" => [&"a_value"],
+        "b" => [&"b_value"],
+        "c" => [&"c_value"],
+        "d" => [&"d_value"],
+        "e" => [&"e_value"],
+        "f" => [&"f_value"]
+        );
+
+        assert!(
+            composer.compose(&stmt.item, &bind_values, &HashMap::new()).is_err(),
+            "handler values need expand_macros()"
+        );
+
+        let (expanded, macro_values) = composer.expand_macros(&stmt.item, &bind_values).expect("expand_macros should work");
+
+        let mut all_values = bind_values.clone();
+
+        all_values.extend(value_binds(&macro_values));
+
+        let (bound_sql, bindings) = composer.compose(&expanded, &all_values, &HashMap::new()).expect("compose should work");
+
+        assert_eq!(bound_sql, expected_bound_sql, "values bound by the handler");
+
+        let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+        let values: Vec<String> = prep_stmt
+            .query_map(&bindings, |row| row.get(0))
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+
+        assert_eq!(values, vec!["d_value"], "exected values");
+
+        let mut conflicting_values = bind_values.clone();
+
+        conflicting_values.insert("excluded".into(), vec![&"a_value"]);
+
+        assert!(
+            composer.expand_macros(&stmt.item, &conflicting_values).is_err(),
+            "name bound by both the request and the handler"
+        );
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1 FROM (:nope(src/tests/values/simple.tql)) AS main;".into()), None).unwrap();
+
+        assert!(
+            composer.compose(&stmt.item, &bind_values, &HashMap::new()).is_err(),
+            "unknown command"
+        );
+    }
+
+    #[test]
+    fn test_named_placeholders() {
+        let conn = setup_db();
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1, col_2, col_3, col_4 FROM (:compose(src/tests/values/double-include.tql)) AS main WHERE col_1 IN (:bind(col_1_values EXPECTING MIN 1)) AND col_4 = :bind(a);".into()), None).unwrap();
+
+        let mut composer = RusqliteComposer::new();
+
+        composer.set_named_placeholders(true);
+
+        let bind_values = bind_values!(&dyn ToSql:
+        "a" => [&"a_value"],
+        "b" => [&"b_value"],
+        "c" => [&"c_value"],
+        "d" => [&"d_value"],
+        "e" => [&"e_value"],
+        "f" => [&"f_value"],
+        "col_1_values" => [&"d_value", &"a_value"]
+        );
+
+        let (bound_sql, bindings) = composer
+            .compose_named(&stmt.item, &bind_values, &HashMap::new())
+            .expect("compose_named should work");
+
+        let expected_bound_sql = "SELECT col_1, col_2, col_3, col_4 FROM ( SELECT :d AS col_1, :f AS col_2, :b AS col_3, :a AS col_4 UNION ALL SELECT :e AS col_1, :d AS col_2, :b AS col_3, :a AS col_4 UNION ALL SELECT :a AS col_1, :b AS col_2, :c AS col_3, :d AS col_4 ) AS main WHERE col_1 IN ( :col_1_values_7, :col_1_values_8 ) AND col_4 = :a;";
+
+        assert_eq!(bound_sql, expected_bound_sql, "named placeholders");
+
+        let names: Vec<&str> = bindings.iter().map(|(name, _)| name.as_str()).collect();
+
+        assert_eq!(
+            names,
+            vec![":d", ":f", ":b", ":a", ":e", ":c", ":col_1_values_7", ":col_1_values_8"],
+            "one value per name"
+        );
+
+        let params: Vec<(&str, &dyn ToSql)> = bindings
+            .iter()
+            .map(|(name, value)| (name.as_str(), *value))
+            .collect();
+
+        let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+        let mut values: Vec<Vec<String>> = vec![];
+
+        let rows = prep_stmt
+            .query_map_named(&params, |row| {
+                Ok((0..4).map(|i| row.get(i).unwrap()).collect::<Vec<String>>())
+            })
+            .unwrap();
+
+        for row in rows {
+            values.push(row.unwrap());
+        }
+
+        assert_eq!(
+            values,
+            vec![vec!["d_value", "f_value", "b_value", "a_value"]],
+            "exected values"
+        );
+
+        composer.set_named_placeholders(false);
+
+        assert!(composer.compose_named(&stmt.item, &bind_values, &HashMap::new()).is_err(), "requires named placeholders");
+    }
+
+    #[test]
+    fn test_named_placeholder_collisions() {
+        let conn = setup_db();
+
+        let select = "SELECT col_1, col_2, col_3, col_4 FROM ( SELECT :e AS col_1, :d AS col_2, :b AS col_3, :a AS col_4 UNION ALL SELECT :a AS col_1, :b AS col_2, :c AS col_3, :d AS col_4 ) AS main";
+
+        let tests = vec![
+            (
+                "col_1 = :bind(ids_7) AND col_2 IN (:bind(ids EXPECTING MIN 1))",
+                format!("{} WHERE col_1 = :ids_7 AND col_2 IN ( :ids_7_1, :ids_8 );", select),
+            ),
+            (
+                "col_2 IN (:bind(ids EXPECTING MIN 1)) AND col_1 = :bind(ids_7)",
+                format!("{} WHERE col_2 IN ( :ids_6, :ids_7 ) AND col_1 = :ids_7_8;", select),
+            ),
+        ];
+
+        for (filter, expected_bound_sql) in tests {
+            let (_remaining, stmt) = parse_template(Span::new(&format!("SELECT col_1, col_2, col_3, col_4 FROM (:compose(src/tests/values/include.tql)) AS main WHERE {};", filter)), None).unwrap();
+
+            let mut composer = RusqliteComposer::new();
+
+            composer.set_named_placeholders(true);
+
+            let bind_values = bind_values!(&dyn ToSql:
+            "a" => [&"a_value"],
+            "b" => [&"b_value"],
+            "c" => [&"c_value"],
+            "d" => [&"d_value"],
+            "e" => [&"e_value"],
+            "ids_7" => [&"e_value"],
+            "ids" => [&"d_value", &"x_value"]
+            );
+
+            let (bound_sql, bindings) = composer
+                .compose_named(&stmt.item, &bind_values, &HashMap::new())
+                .expect("compose_named should work");
+
+            assert_eq!(bound_sql, expected_bound_sql, "each value has its own name");
+
+            let params: Vec<(&str, &dyn ToSql)> = bindings
+                .iter()
+                .map(|(name, value)| (name.as_str(), *value))
+                .collect();
+
+            let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+            let values: Vec<String> = prep_stmt
+                .query_map_named(&params, |row| row.get(0))
+                .unwrap()
+                .map(|row| row.unwrap())
+                .collect();
+
+            assert_eq!(values, vec!["e_value"], "values bound to their own names");
+        }
+    }
+
+    #[test]
+    fn test_include_mock_multi_value_bind() {
+        let conn = setup_db();
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT * FROM (:compose(src/tests/values/double-include.tql)) AS main WHERE col_1 in (:bind(col_1_values EXPECTING MIN 1)) AND col_3 IN (:bind(col_3_values EXPECTING MIN 1));".into()), None).unwrap();
+
+        let expected_bound_sql = "SELECT * FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 ) AS main WHERE col_1 in ( ?9, ?10 ) AND col_3 IN ( ?11, ?12 );";
+
+        let expected_values = vec![
+            vec!["d_value", "f_value", "b_value", "a_value"],
+            vec!["ee_value", "dd_value", "bb_value", "aa_value"],
+        ];
+
+        let composer = RusqliteComposer::new();
+
+        let bind_values = bind_values!(&dyn ToSql:
+        "a" => [&"a_value"],
+        "b" => [&"b_value"],
+        "c" => [&"c_value"],
+        "d" => [&"d_value"],
+        "e" => [&"e_value"],
+        "f" => [&"f_value"],
+        "col_1_values" => [&"ee_value", &"d_value"],
+        "col_3_values" => [&"bb_value", &"b_value"]
+        );
+
+        let mock_values = mock_path_values!(&dyn ToSql: "src/tests/values/include.tql" => [{
+            "col_1" => &"ee_value",
+            "col_2" => &"dd_value",
+            "col_3" => &"bb_value",
+            "col_4" => &"aa_value"
+        }]);
+
+        let (bound_sql, bindings) = ComposerContext::new(&composer, &bind_values, &mock_values)
+            .compose_statement(&stmt, 1, false)
+            .expect("compose_statement should work");
+
+        let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+        let mut values: Vec<Vec<String>> = vec![];
+
+        let rows = prep_stmt
+            .query_map(&bindings, |row| {
+                (0..4).fold(Ok(Vec::new()), |acc, i| {
+                    if let Ok(mut acc) = acc {
+                        acc.push(row.get(i).unwrap());
+                        Ok(acc)
+                    }
+                    else {
+                        acc
+                    }
+                })
+            })
+            .unwrap();
+
+        for row in rows {
+            values.push(row.unwrap());
+        }
+
+        assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
+        assert_eq!(values, expected_values, "exected values");
+    }
+
+    #[test]
+    fn test_mock_double_include_multi_value_bind() {
+        let conn = setup_db();
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT * FROM (:compose(src/tests/values/double-include.tql)) AS main WHERE col_1 in (:bind(col_1_values EXPECTING MIN 1)) AND col_3 IN (:bind(col_3_values EXPECTING MIN 1));".into()), None).unwrap();
+
+        let expected_bound_sql = "SELECT * FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 UNION ALL SELECT ?9 AS col_1, ?10 AS col_2, ?11 AS col_3, ?12 AS col_4 ) AS main WHERE col_1 in ( ?13, ?14 ) AND col_3 IN ( ?15, ?16 );";
+
+        let expected_values = vec![
+            vec!["dd_value", "ff_value", "bb_value", "aa_value"],
+            vec!["dd_value", "ff_value", "bb_value", "aa_value"],
+            vec!["aa_value", "bb_value", "cc_value", "dd_value"],
+        ];
+
+        let composer = RusqliteComposer::new();
+
+        let bind_values = bind_values!(&dyn ToSql:
+        "a" => [&"a_value"],
+        "b" => [&"b_value"],
+        "c" => [&"c_value"],
+        "d" => [&"d_value"],
+        "e" => [&"e_value"],
+        "f" => [&"f_value"],
+        "col_1_values" => [&"dd_value", &"aa_value"],
+        "col_3_values" => [&"bb_value", &"cc_value"]
+        );
+
+        let mock_values = mock_path_values!(&dyn ToSql: "src/tests/values/double-include.tql" => [{
+            "col_1" => &"dd_value",
+            "col_2" => &"ff_value",
+            "col_3" => &"bb_value",
+            "col_4" => &"aa_value"
+            },
+            {
+            "col_1" => &"dd_value",
+            "col_2" => &"ff_value",
+            "col_3" => &"bb_value",
+            "col_4" => &"aa_value"
+            },
+            {
+            "col_1" => &"aa_value",
+            "col_2" => &"bb_value",
+            "col_3" => &"cc_value",
+            "col_4" => &"dd_value"
+        }]);
+
+        let (bound_sql, bindings) = ComposerContext::new(&composer, &bind_values, &mock_values)
+            .compose_statement(&stmt, 1, false)
+            .expect("compose_statement should work");
+
+        let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+        let mut values: Vec<Vec<String>> = vec![];
+
+        let rows = prep_stmt
+            .query_map(&bindings, |row| {
+                (0..4).fold(Ok(Vec::new()), |acc, i| {
+                    if let Ok(mut acc) = acc {
+                        acc.push(row.get(i).unwrap());
+                        Ok(acc)
+                    }
+                    else {
+                        acc
+                    }
+                })
+            })
+            .unwrap();
+
+        for row in rows {
+            values.push(row.unwrap());
+        }
+
+        assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
+        assert_eq!(values, expected_values, "exected values");
+    }
+
+    #[test]
+    fn test_mock_db_object() {
+        let conn = setup_db();
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT * FROM main WHERE col_1 in (:bind(col_1_values EXPECTING MIN 1)) AND col_3 IN (:bind(col_3_values EXPECTING MIN 1));".into()), None).unwrap();
+
+        let expected_bound_sql = "SELECT * FROM ( SELECT ?1 AS col_1, ?2 AS col_2, ?3 AS col_3, ?4 AS col_4 UNION ALL SELECT ?5 AS col_1, ?6 AS col_2, ?7 AS col_3, ?8 AS col_4 UNION ALL SELECT ?9 AS col_1, ?10 AS col_2, ?11 AS col_3, ?12 AS col_4 ) AS main WHERE col_1 in ( ?13, ?14 ) AND col_3 IN ( ?15, ?16 );";
+
+        let expected_values = vec![
+            vec!["dd_value", "ff_value", "bb_value", "aa_value"],
+            vec!["dd_value", "ff_value", "bb_value", "aa_value"],
+            vec!["aa_value", "bb_value", "cc_value", "dd_value"],
+        ];
+
+        let composer = RusqliteComposer::new();
+
+        let bind_values = bind_values!(&dyn ToSql:
+        "a" => [&"a_value"],
+        "b" => [&"b_value"],
+        "c" => [&"c_value"],
+        "d" => [&"d_value"],
+        "e" => [&"e_value"],
+        "f" => [&"f_value"],
+        "col_1_values" => [&"dd_value", &"aa_value"],
+        "col_3_values" => [&"bb_value", &"cc_value"]
+        );
+
+        let mock_values = mock_db_object_values!(&dyn ToSql: "main" => [{
+            "col_1" => &"dd_value",
+            "col_2" => &"ff_value",
+            "col_3" => &"bb_value",
+            "col_4" => &"aa_value"
+        },
+            {
+            "col_1" => &"dd_value",
+            "col_2" => &"ff_value",
+            "col_3" => &"bb_value",
+            "col_4" => &"aa_value"
+        },
+            {
+            "col_1" => &"aa_value",
+            "col_2" => &"bb_value",
+            "col_3" => &"cc_value",
+            "col_4" => &"dd_value"
+        }]);
+
+        let (bound_sql, bindings) = ComposerContext::new(&composer, &bind_values, &mock_values)
+            .compose_statement(&stmt, 1, false)
+            .expect("compose_statement should work");
+
+        let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+        let mut values: Vec<Vec<String>> = vec![];
+
+        let rows = prep_stmt
+            .query_map(&bindings, |row| {
+                (0..4).fold(Ok(Vec::new()), |acc, i| {
+                    if let Ok(mut acc) = acc {
+                        acc.push(row.get(i).unwrap());
+                        Ok(acc)
+                    }
+                    else {
+                        acc
+                    }
+                })
+            })
+            .unwrap();
+
+        for row in rows {
+            values.push(row.unwrap());
+        }
+
+        assert_eq!(bound_sql, expected_bound_sql, "preparable statements match");
+        assert_eq!(values, expected_values, "exected values");
+    }
+
+    #[test]
+    fn it_composes_from_connection() {
+        let conn = setup_db();
+
+        let stmt = SqlComposition::from_path_name("src/tests/values/simple.tql".into()).unwrap();
+
+        let (mut prep_stmt, bindings) = conn
+            .composition(&stmt.item)
+            .bind("a", &"a_value")
+            .bind("b", &"b_value")
+            .bind("c", &"c_value")
+            .bind("d", &"d_value")
+            .prepare()
+            .unwrap();
+
+        let mut values: Vec<Vec<String>> = vec![];
+
+        let rows = prep_stmt
+            .query_map(&bindings, |row| {
                 (0..4).fold(Ok(Vec::new()), |acc, i| {
                     if let Ok(mut acc) = acc {
                         acc.push(row.get(i).unwrap());
@@ -919,4 +2286,301 @@ mod tests {
 
         assert_eq!(values, expected, "exected values");
     }
+
+    #[test]
+    fn test_compose_request() {
+        let conn = setup_db();
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT id, name FROM person WHERE id IN (:bind(ids EXPECTING MIN 1)) AND name <> :bind(name);".into()), None).unwrap();
+
+        let ids = vec![1i64, 3i64];
+
+        let (steven, kenny, stan) = ("Steven", "Kenny", "Stan");
+
+        let rows = vec![
+            vec![("id", &1i64 as &dyn ToSql), ("name", &steven as &dyn ToSql)],
+            vec![("id", &2i64 as &dyn ToSql), ("name", &kenny as &dyn ToSql)],
+            vec![("id", &3i64 as &dyn ToSql), ("name", &stan as &dyn ToSql)],
+        ];
+
+        let request = conn
+            .composition(&stmt.item)
+            .bind_all("ids", &ids)
+            .bind("name", &kenny)
+            .mock_table("person", rows);
+
+        let (bound_sql, _bindings) = request.compose().expect("compose should work");
+
+        assert_eq!(bound_sql, "SELECT id, name FROM ( SELECT ?1 AS id, ?2 AS name UNION ALL SELECT ?3 AS id, ?4 AS name UNION ALL SELECT ?5 AS id, ?6 AS name ) AS person WHERE id IN ( ?7, ?8 ) AND name <> ?9;");
+
+        let (mut prep_stmt, bindings) = request.prepare().expect("prepare should work");
+
+        let people: Vec<(i64, String)> = prep_stmt
+            .query_map(&bindings, |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+
+        assert_eq!(
+            people,
+            vec![(1, "Steven".to_string()), (3, "Stan".to_string())],
+            "mocked rows"
+        );
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1 FROM (:compose(src/tests/values/simple.tql)) AS main WHERE col_1 <> :bind(name);".into()), None).unwrap();
+
+        let (bound_sql, bindings) = conn
+            .composition(&stmt.item)
+            .bind("name", &kenny)
+            .mock_path(
+                "src/tests/values/simple.tql",
+                vec![vec![("col_1", &steven as &dyn ToSql)]],
+            )
+            .compose()
+            .expect("compose should work");
+
+        assert_eq!(
+            bound_sql,
+            "SELECT col_1 FROM ( SELECT ?1 AS col_1 ) AS main WHERE col_1 <> ?2;"
+        );
+        assert_eq!(bindings.len(), 2, "mocked and bound values");
+    }
+
+    #[test]
+    fn test_owned_values() {
+        let conn = Connection::open_in_memory().unwrap();
+
+        conn.execute(
+            "CREATE TABLE event (
+               id              INTEGER PRIMARY KEY,
+               active          INTEGER NOT NULL,
+               day             TEXT NOT NULL,
+               amount          TEXT NOT NULL
+             )",
+            NO_PARAMS,
+        )
+        .unwrap();
+
+        let (_remaining, insert_stmt) = parse_template(Span::new("INSERT INTO event (id, active, day, amount) VALUES (:bind(id), :bind(active), :bind(day), :bind(amount));".into()), None).unwrap();
+
+        let mut values: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+
+        values.insert("id".to_string(), vec![Value::Integer(1)]);
+        values.insert("active".to_string(), vec![Value::Bool(true)]);
+        values.insert(
+            "day".to_string(),
+            vec![Value::Date(NaiveDate::from_ymd_opt(2019, 7, 4).unwrap())],
+        );
+        values.insert(
+            "amount".to_string(),
+            vec![Value::Decimal(Decimal::new(-123456, 3))],
+        );
+
+        let composer = RusqliteComposer::new();
+
+        let bind_values = value_binds(&values);
+
+        let (bound_sql, bindings) = composer
+            .compose(&insert_stmt.item, &bind_values, &HashMap::new())
+            .expect("compose should work");
+
+        conn.execute(&bound_sql, &bindings).unwrap();
+
+        let (_remaining, select_stmt) = parse_template(Span::new("SELECT active, day, amount FROM event WHERE id = :bind(id) AND active = :bind(active) AND day = :bind(day);".into()), None).unwrap();
+
+        let (bound_sql, bindings) = composer
+            .compose(&select_stmt.item, &bind_values, &HashMap::new())
+            .expect("compose should work");
+
+        let row: (bool, String, String) = conn
+            .query_row(&bound_sql, &bindings, |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .unwrap();
+
+        assert_eq!(
+            row,
+            (true, "2019-07-04".to_string(), "-123.456".to_string()),
+            "typed values round trip"
+        );
+
+        let (mut prep_stmt, bindings) = conn
+            .composition(&select_stmt.item)
+            .bind_map(&values)
+            .prepare()
+            .expect("prepare should work");
+
+        let rows = prep_stmt.query_map(&bindings, |_row| Ok(())).unwrap().count();
+
+        assert_eq!(rows, 1, "owned values bound on a connection");
+
+        let direct = DirectComposer::new();
+
+        let (bound_sql, _bindings) = direct
+            .compose(
+                &insert_stmt.item,
+                &value_binds(&values),
+                &HashMap::new(),
+            )
+            .expect("compose should work");
+
+        assert_eq!(
+            bound_sql,
+            "INSERT INTO event (id, active, day, amount) VALUES ( 1, 1, '2019-07-04', -123.456 );",
+            "same values composed as literals"
+        );
+    }
+
+    #[cfg(feature = "composer-serde")]
+    #[test]
+    fn test_query_deserialize() {
+        use serde_derive::Deserialize;
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Named {
+            id:       i64,
+            name:     String,
+            nickname: Option<String>,
+        }
+
+        let conn = setup_db();
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT id, name, nickname FROM person WHERE id IN (:bind(ids EXPECTING MIN 1));".into()), None).unwrap();
+
+        let ids = vec![1i64, 2i64];
+
+        let (steven, kenny, nickname) = ("Steven", "Kenny", "Mysterion");
+
+        let rows = vec![
+            vec![("id", &1i64 as &dyn ToSql), ("name", &steven as &dyn ToSql), ("nickname", &None::<String> as &dyn ToSql)],
+            vec![("id", &2i64 as &dyn ToSql), ("name", &kenny as &dyn ToSql), ("nickname", &nickname as &dyn ToSql)],
+        ];
+
+        let people: Vec<Named> = conn
+            .composition(&stmt.item)
+            .bind_all("ids", &ids)
+            .mock_table("person", rows)
+            .query()
+            .expect("query should work");
+
+        assert_eq!(
+            people,
+            vec![
+                Named {
+                    id:       1,
+                    name:     "Steven".to_string(),
+                    nickname: None,
+                },
+                Named {
+                    id:       2,
+                    name:     "Kenny".to_string(),
+                    nickname: Some("Mysterion".to_string()),
+                },
+            ],
+            "rows deserialized by column name, NULL as None"
+        );
+
+        let result: Result<Vec<(String, i64)>, ()> = conn
+            .composition(&stmt.item)
+            .bind_all("ids", &ids)
+            .mock_table(
+                "person",
+                vec![vec![("id", &1i64 as &dyn ToSql), ("name", &steven as &dyn ToSql), ("nickname", &nickname as &dyn ToSql)]],
+            )
+            .query();
+
+        assert!(result.is_err(), "rows which don't match the type");
+    }
+
+    #[cfg(feature = "composer-serde")]
+    #[test]
+    fn test_named_placeholders_nested() {
+        use crate::types::value::to_bind_values;
+        use serde_derive::Serialize;
+
+        #[derive(Serialize)]
+        struct Page {
+            limit:  i64,
+            offset: i64,
+        }
+
+        #[derive(Serialize)]
+        struct Search {
+            id:   i64,
+            page: Page,
+        }
+
+        let conn = setup_db();
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1 FROM (SELECT 1 AS col_1 UNION ALL SELECT 2 UNION ALL SELECT 3) AS main WHERE col_1 <> :bind(id) ORDER BY col_1 :paginate(page.limit, page.offset);".into()), None).unwrap();
+
+        let mut composer = RusqliteComposer::new();
+
+        composer.set_named_placeholders(true);
+
+        let search = Search {
+            id:   4,
+            page: Page {
+                limit:  1,
+                offset: 1,
+            },
+        };
+
+        let values = to_bind_values(&search).expect("to_bind_values should work");
+
+        let bind_values = value_binds(&values);
+
+        let (bound_sql, bindings) = composer
+            .compose_named(&stmt.item, &bind_values, &HashMap::new())
+            .expect("compose_named should work");
+
+        assert_eq!(
+            bound_sql,
+            "SELECT col_1 FROM (SELECT 1 AS col_1 UNION ALL SELECT 2 UNION ALL SELECT 3) AS main WHERE col_1 <> :id ORDER BY col_1 LIMIT :page__limit OFFSET :page__offset;",
+            "dotted names written with __"
+        );
+
+        let names: Vec<&str> = bindings.iter().map(|(name, _)| name.as_str()).collect();
+
+        assert_eq!(names, vec![":id", ":page__limit", ":page__offset"], "one value per name");
+
+        let params: Vec<(&str, &dyn ToSql)> = bindings
+            .iter()
+            .map(|(name, value)| (name.as_str(), *value))
+            .collect();
+
+        let mut prep_stmt = conn.prepare(&bound_sql).unwrap();
+
+        let values: Vec<i64> = prep_stmt
+            .query_map_named(&params, |row| row.get(0))
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+
+        assert_eq!(values, vec![2], "second row of the page");
+    }
+
+    #[cfg(feature = "composer-serde")]
+    #[test]
+    fn test_row_value_blob() {
+        use super::row_value;
+        use serde_value::Value as SerdeValue;
+
+        let conn = setup_db();
+
+        let column_names = vec!["t".to_string(), "b".to_string()];
+
+        let row = conn
+            .query_row("SELECT 'hi', X'6869'", NO_PARAMS, |row| {
+                Ok(row_value(&column_names, row))
+            })
+            .unwrap();
+
+        let mut expected = BTreeMap::new();
+
+        expected.insert(SerdeValue::String("t".into()), SerdeValue::String("hi".into()));
+        expected.insert(SerdeValue::String("b".into()), SerdeValue::Bytes(b"hi".to_vec()));
+
+        assert_eq!(row, SerdeValue::Map(expected), "a blob of valid UTF-8 is still bytes");
+    }
 }
@@ -0,0 +1,481 @@
+use super::dialect::{DialectComposer, PlaceholderStyle};
+use super::{value_binds, Binds, ComposedPage, Composer, IdentifierQuoting, MacroHandler, Mocks,
+            PaginationStyle};
+
+use crate::types::value::{LiteralStyle, ToValue, Value};
+use crate::types::{SqlComposition, SqlPagination};
+
+/// A composer for owned `Value`s, which can be handed to any of the drivers. It can be stored in a
+/// struct or shared between threads like any other composer, without having to borrow the values
+/// of each request for as long as the composer lives.
+pub struct SharedComposer {
+    composer: DialectComposer<'static>,
+}
+
+impl SharedComposer {
+    pub fn new(placeholder_style: PlaceholderStyle) -> Self {
+        Self {
+            composer: DialectComposer::new(placeholder_style),
+        }
+    }
+
+    /// `?1` placeholders, with the parameter limit of SQLite 3.32.0 and later. Older versions
+    /// need `set_max_bind_params(999)`.
+    pub fn sqlite() -> Self {
+        let mut composer = Self::new(PlaceholderStyle::NumberedQuestion);
+
+        composer.set_unbounded_limit(Some("-1".into()));
+        composer.set_max_bind_params(32766);
+
+        composer
+    }
+
+    /// `?` placeholders, `` `name` `` identifiers and MySQL's backslash escaped literals.
+    pub fn mysql() -> Self {
+        let mut composer = Self::new(PlaceholderStyle::Question);
+
+        composer.set_identifier_quoting(IdentifierQuoting::Backtick);
+        composer.set_literal_style(LiteralStyle::MySql);
+        composer.set_unbounded_limit(Some("18446744073709551615".into()));
+
+        composer
+    }
+
+    /// `$1` placeholders and PostgreSQL literals. Unlike `PostgresComposer`, a name bound more
+    /// than once gets a new `$n` each time rather than reusing its first, and arrays bound to an IN
+    /// list aren't sent as one `= ANY($1)` parameter.
+    pub fn postgres() -> Self {
+        let mut composer = Self::new(PlaceholderStyle::Dollar);
+
+        composer.set_literal_style(LiteralStyle::Postgres);
+
+        composer
+    }
+
+    /// `@p1` placeholders, `[name]` identifiers and OFFSET/FETCH pagination, with SQL Server's
+    /// limit of 2100 parameters.
+    pub fn sql_server() -> Self {
+        Self {
+            composer: DialectComposer::sql_server(),
+        }
+    }
+
+    /// `:1` placeholders, `"name"` identifiers and OFFSET/FETCH pagination, with table aliases
+    /// written without AS and mocked rows selected FROM DUAL.
+    pub fn oracle() -> Self {
+        Self {
+            composer: DialectComposer::oracle(),
+        }
+    }
+
+    pub fn set_identifier_quoting(&mut self, quoting: IdentifierQuoting) {
+        self.composer.set_identifier_quoting(quoting);
+    }
+
+    pub fn set_pagination_style(&mut self, style: PaginationStyle) {
+        self.composer.set_pagination_style(style);
+    }
+
+    pub fn set_unbounded_limit(&mut self, limit: Option<String>) {
+        self.composer.set_unbounded_limit(limit);
+    }
+
+    pub fn set_table_alias_as(&mut self, table_alias_as: bool) {
+        self.composer.set_table_alias_as(table_alias_as);
+    }
+
+    pub fn set_dummy_table(&mut self, table: Option<String>) {
+        self.composer.set_dummy_table(table);
+    }
+
+    pub fn set_literal_style(&mut self, style: LiteralStyle) {
+        self.composer.set_literal_style(style);
+    }
+
+    pub fn set_max_bind_params(&mut self, max: usize) {
+        self.composer.set_max_bind_params(max);
+    }
+
+    pub fn max_bind_params(&self) -> usize {
+        self.composer.max_bind_params()
+    }
+
+    /// How the dialect writes a value as a literal, for sql the caller writes itself.
+    pub fn literal_style(&self) -> LiteralStyle {
+        self.composer.literal_style()
+    }
+
+    pub fn insert_macro(&mut self, name: String, handler: Box<dyn MacroHandler>) {
+        self.composer.insert_macro(name, handler);
+    }
+
+    /// Expands the macros of `s`, as `Composer::expand_macros()`, along with the values bound to
+    /// them and `values`.
+    fn expand_macros(
+        &self,
+        s: &SqlComposition,
+        values: &Binds<Value>,
+    ) -> Result<(SqlComposition, Binds<Value>), ()> {
+        let (expanded, macro_values) =
+            self.composer.expand_macros(s, &value_binds(values))?;
+
+        let mut values = values.clone();
+
+        values.extend(macro_values);
+
+        Ok((expanded, values))
+    }
+
+    pub fn compose(
+        &self,
+        s: &SqlComposition,
+        values: &Binds<Value>,
+        mocks: &Mocks<Value>,
+    ) -> Result<(String, Vec<Value>), ()> {
+        let (s, values) = self.expand_macros(s, values)?;
+
+        let (sql, bindings) = self.composer.compose(
+            &s,
+            &value_binds(&values),
+            &DialectComposer::value_mocks(mocks),
+        )?;
+
+        Ok((sql, owned_values(bindings)?))
+    }
+
+    /// Composes `s` with `PlaceholderStyle::Named`, pairing each value with its placeholder.
+    pub fn compose_named(
+        &self,
+        s: &SqlComposition,
+        values: &Binds<Value>,
+        mocks: &Mocks<Value>,
+    ) -> Result<(String, Vec<(String, Value)>), ()> {
+        let (s, values) = self.expand_macros(s, values)?;
+
+        let (sql, bindings) = self.composer.compose_named(
+            &s,
+            &value_binds(&values),
+            &DialectComposer::value_mocks(mocks),
+        )?;
+
+        let mut named_values = vec![];
+
+        for (name, value) in bindings {
+            named_values.push((name, value.to_value()?));
+        }
+
+        Ok((sql, named_values))
+    }
+
+    /// Composes a page of `s` and a statement counting its rows, as `Composer::compose_page()`.
+    pub fn compose_page(
+        &self,
+        s: &SqlComposition,
+        page: &SqlPagination,
+        values: &Binds<Value>,
+        mocks: &Mocks<Value>,
+    ) -> Result<ComposedPage<Value>, ()> {
+        let (s, values) = self.expand_macros(s, values)?;
+
+        let ((page_sql, page_bindings), (count_sql, count_bindings)) = self.composer.compose_page(
+            &s,
+            page,
+            &value_binds(&values),
+            &DialectComposer::value_mocks(mocks),
+        )?;
+
+        Ok((
+            (page_sql, owned_values(page_bindings)?),
+            (count_sql, owned_values(count_bindings)?),
+        ))
+    }
+
+    /// Splits the rows bound to `rows` across statements, as `Composer::compose_chunked()`.
+    pub fn compose_chunked(
+        &self,
+        s: &SqlComposition,
+        rows: &str,
+        values: &Binds<Value>,
+        mocks: &Mocks<Value>,
+    ) -> Result<Vec<(String, Vec<Value>)>, ()> {
+        let (s, values) = self.expand_macros(s, values)?;
+
+        let mut statements = vec![];
+
+        for (sql, bindings) in self.composer.compose_chunked(
+            &s,
+            rows,
+            &value_binds(&values),
+            &DialectComposer::value_mocks(mocks),
+        )? {
+            statements.push((sql, owned_values(bindings)?));
+        }
+
+        Ok(statements)
+    }
+}
+
+fn owned_values(bindings: Vec<&dyn ToValue>) -> Result<Vec<Value>, ()> {
+    bindings.into_iter().map(|v| v.to_value()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PlaceholderStyle, SharedComposer};
+
+    use crate::composer::MacroHandler;
+
+    use crate::mock_db_object_values;
+
+    use crate::parser::parse_template;
+
+    use crate::types::value::{LiteralStyle, Value};
+    use crate::types::{Span, SqlBinding, SqlComposition, SqlCompositionAlias, SqlDbObject};
+
+    use std::collections::{BTreeMap, HashMap};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_values_per_call() {
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1 FROM t AS main WHERE col_1 IN (:bind(ids EXPECTING MIN 1)) AND col_2 = :bind(name);".into()), None).unwrap();
+
+        let composer = SharedComposer::postgres();
+
+        let mut values: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+
+        values.insert("ids".to_string(), vec![Value::Integer(1), Value::Integer(2)]);
+        values.insert("name".to_string(), vec![Value::Text("a".into())]);
+
+        let (bound_sql, bindings) = composer
+            .compose(&stmt.item, &values, &HashMap::new())
+            .expect("compose should work");
+
+        assert_eq!(
+            bound_sql,
+            "SELECT col_1 FROM t AS main WHERE col_1 IN ( $1, $2 ) AND col_2 = $3;"
+        );
+        assert_eq!(
+            bindings,
+            vec![Value::Integer(1), Value::Integer(2), Value::Text("a".into())],
+            "owned values"
+        );
+
+        values.insert("ids".to_string(), vec![Value::Integer(3)]);
+
+        let (bound_sql, bindings) = composer
+            .compose(&stmt.item, &values, &HashMap::new())
+            .expect("compose should work");
+
+        assert_eq!(
+            bound_sql,
+            "SELECT col_1 FROM t AS main WHERE col_1 IN ( $1 ) AND col_2 = $2;"
+        );
+        assert_eq!(bindings.len(), 2, "values of the second call only");
+
+        assert!(
+            composer.compose(&stmt.item, &BTreeMap::new(), &HashMap::new()).is_err(),
+            "values aren't kept between calls"
+        );
+    }
+
+    #[test]
+    fn test_mocks_per_call() {
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1 FROM main WHERE col_1 = :bind(id);".into()), None).unwrap();
+
+        let composer = SharedComposer::sqlite();
+
+        let mut values: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+
+        values.insert("id".to_string(), vec![Value::Integer(2)]);
+
+        let mocks = mock_db_object_values!(Value: "main" => [{"col_1" => Value::Integer(1)}, {"col_1" => Value::Integer(2)}]);
+
+        let (bound_sql, bindings) = composer
+            .compose(&stmt.item, &values, &mocks)
+            .expect("compose should work");
+
+        assert_eq!(
+            bound_sql,
+            "SELECT col_1 FROM ( SELECT ?1 AS col_1 UNION ALL SELECT ?2 AS col_1 ) AS main WHERE col_1 = ?3;"
+        );
+        assert_eq!(
+            bindings,
+            vec![Value::Integer(1), Value::Integer(2), Value::Integer(2)],
+            "mocked values ahead of the bound value"
+        );
+
+        let (bound_sql, _bindings) = composer
+            .compose(&stmt.item, &values, &HashMap::new())
+            .expect("compose should work");
+
+        assert_eq!(
+            bound_sql,
+            "SELECT col_1 FROM main WHERE col_1 = ?1;",
+            "mocks aren't kept between calls"
+        );
+    }
+
+    #[test]
+    fn test_literal_styles() {
+        assert_eq!(SharedComposer::sqlite().literal_style(), LiteralStyle::Standard);
+        assert_eq!(SharedComposer::mysql().literal_style(), LiteralStyle::MySql);
+        assert_eq!(SharedComposer::postgres().literal_style(), LiteralStyle::Postgres);
+
+        assert_eq!(
+            Value::Text("a\\b".into()).to_sql_text(SharedComposer::mysql().literal_style()),
+            Ok("'a\\\\b'".to_string()),
+            "backslashes escaped for mysql"
+        );
+    }
+
+    #[test]
+    fn test_offset_without_limit() {
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1 FROM t AS main ORDER BY col_1 :paginate(limit, offset);".into()), None).unwrap();
+
+        let mut values: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+
+        values.insert("offset".to_string(), vec![Value::Integer(20)]);
+
+        let (bound_sql, _bindings) = SharedComposer::mysql()
+            .compose(&stmt.item, &values, &HashMap::new())
+            .expect("compose should work");
+
+        assert_eq!(
+            bound_sql,
+            "SELECT col_1 FROM t AS main ORDER BY col_1 LIMIT 18446744073709551615 OFFSET ?;",
+            "mysql requires a limit"
+        );
+
+        let (bound_sql, _bindings) = SharedComposer::postgres()
+            .compose(&stmt.item, &values, &HashMap::new())
+            .expect("compose should work");
+
+        assert_eq!(
+            bound_sql,
+            "SELECT col_1 FROM t AS main ORDER BY col_1 OFFSET $1;",
+            "postgres takes an offset alone"
+        );
+    }
+
+    #[test]
+    fn test_shared_between_threads() {
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1 FROM t AS main WHERE col_1 = :bind(id) AND col_2 = :bind(name);".into()), None).unwrap();
+
+        let composer = Arc::new(SharedComposer::new(PlaceholderStyle::Named));
+        let stmt = Arc::new(stmt.item);
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let composer = Arc::clone(&composer);
+                let stmt = Arc::clone(&stmt);
+
+                thread::spawn(move || {
+                    let mut values: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+
+                    values.insert("id".to_string(), vec![Value::Integer(i)]);
+                    values.insert("name".to_string(), vec![Value::Text(format!("name_{}", i))]);
+
+                    composer
+                        .compose_named(&stmt, &values, &HashMap::new())
+                        .expect("compose_named should work")
+                })
+            })
+            .collect();
+
+        for (i, handle) in handles.into_iter().enumerate() {
+            let (bound_sql, bindings) = handle.join().unwrap();
+
+            assert_eq!(
+                bound_sql,
+                "SELECT col_1 FROM t AS main WHERE col_1 = :id AND col_2 = :name;"
+            );
+            assert_eq!(
+                bindings,
+                vec![
+                    (":id".to_string(), Value::Integer(i as i64)),
+                    (":name".to_string(), Value::Text(format!("name_{}", i)))
+                ],
+                "values of thread {}",
+                i
+            );
+        }
+    }
+
+    /// Limits a composition to the rows after a cutoff it binds itself.
+    struct SinceMacro;
+
+    impl MacroHandler for SinceMacro {
+        fn expand(
+            &self,
+            composition: &SqlComposition,
+        ) -> Result<(SqlComposition, BTreeMap<String, Vec<Value>>), ()> {
+            let mut out = SqlComposition::default();
+
+            out.push_generated_literal("SELECT * FROM (", None).unwrap();
+
+            for of in &composition.of {
+                out.push_generated_compose(of, &composition.aliases, None)
+                    .unwrap();
+            }
+
+            out.push_generated_literal(") AS since_main WHERE col_1 >", None)
+                .unwrap();
+
+            out.push_generated_binding(
+                SqlBinding::new("since".into(), false, None, None, false).unwrap(),
+                None,
+            )
+            .unwrap();
+
+            let mut values = BTreeMap::new();
+
+            values.insert("since".to_string(), vec![Value::Integer(10)]);
+
+            Ok((out, values))
+        }
+    }
+
+    #[test]
+    fn test_macro_values() {
+        let (_remaining, stmt) = parse_template(Span::new("SELECT col_1 FROM (:since(src/tests/values/simple.tql)) AS main WHERE col_2 = :bind(name);".into()), None).unwrap();
+
+        let mut composer = SharedComposer::postgres();
+
+        composer.insert_macro("SINCE".into(), Box::new(SinceMacro));
+
+        let mut values: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+
+        for (i, name) in ["a", "b", "c", "d", "name"].iter().enumerate() {
+            values.insert(name.to_string(), vec![Value::Integer(i as i64)]);
+        }
+
+        let (bound_sql, bindings) = composer
+            .compose(&stmt.item, &values, &HashMap::new())
+            .expect("compose should work");
+
+        assert_eq!(
+            bound_sql,
+            "SELECT col_1 FROM ( SELECT * FROM ( SELECT $1 AS col_1, $2 AS col_2, $3 AS col_3, $4 AS col_4 ) AS since_main WHERE col_1 > $5 ) AS main WHERE col_2 = $6;"
+        );
+        assert_eq!(
+            bindings,
+            vec![
+                Value::Integer(0),
+                Value::Integer(1),
+                Value::Integer(2),
+                Value::Integer(3),
+                Value::Integer(10),
+                Value::Integer(4),
+            ],
+            "values bound by the handler get placeholders"
+        );
+
+        values.insert("since".to_string(), vec![Value::Integer(20)]);
+
+        assert!(
+            composer.compose(&stmt.item, &values, &HashMap::new()).is_err(),
+            "name bound by both the request and the handler"
+        );
+    }
+}
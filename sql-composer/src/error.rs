@@ -8,6 +8,10 @@ error_chain! {
             description("no terminating character found")
                 display("expected termination character, none found")
         }
+        InvalidBindValue(t: String) {
+            description("a value can't be bound by name")
+                display("unable to bind value: '{}'", t)
+        }
     }
 
     foreign_links {
@@ -15,3 +19,10 @@ error_chain! {
         Io(std::io::Error);
     }
 }
+
+#[cfg(feature = "composer-serde")]
+impl serde::ser::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        ErrorKind::InvalidBindValue(msg.to_string()).into()
+    }
+}
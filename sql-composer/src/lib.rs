@@ -14,11 +14,75 @@
 ///
 /// `:compose(pathbuf or string)`.
 /// * `:bind(var_name)` ::  handles SQL named bind parameters
+/// * `:bind_rows(var_name COLUMNS a, b)` :: handles a list of row values, grouping the placeholders
+///   of each row as `(?, ?), (?, ?)`
+/// * `:values(var_name)` :: expands to `VALUES (?, ?), (?, ?)` for a list of rows, with the row
+///   width taken from the column list before it or given as `COLUMNS a, b`
 /// * `:compose(pathbuf or string)` :: composes a complete statement of SQL into the current SQL
+/// * `:with(name of path, ...)` :: composes each path as a named common table expression ahead
+///   of the statement
+/// * `:exists(path)`, `:not_exists(path)` :: wraps a composed statement in an EXISTS predicate
+/// * `:if(name) ... :else ... :endif` :: includes the first block when `name` has values bound,
+///   otherwise the optional `:else` block
+/// * `:and(sql)`, `:or(sql)` :: includes sql and its conjunction only when every binding in it has
+///   values; a WHERE left without any conditions is dropped, and conditions holding an OR are put
+///   in parens so an `:and()` applies to all of them
+/// * `:ident(name ALLOW a, b)` :: substitutes the identifier bound to `name`, quoted for the
+///   dialect, after checking it is one of the allowed identifiers
+/// * `:order_by(name ALLOW a, b DEFAULT a DESC)` :: builds an ORDER BY from the `column [ASC|DESC]`
+///   terms bound to `name`, each checked against the allowed columns, falling back to the default
+/// * `:paginate(limit, offset)`, `:paginate_after(column, cursor, limit)` :: limits the rows in the
+///   composer's dialect, with keyset pagination adding `column > cursor` to the WHERE clause
 ///
-/// * A composer can be reused for a single statement multiple times. You could call compose()
-/// multiple times with different sets of values and get SQL that looks quite different due to the
-/// difference in number of placeholders and size/shape of the bind values returned as well.
+/// * `ComposerConnection::composition()` starts a request on a connection, adding values with
+///   `bind()`/`bind_all()` and mocked rows with `mock_table()`/`mock_path()` before `prepare()`
+///   composes it and prepares the statement.
+///
+/// * `#[derive(BindValues)]`, with the `derive` feature, binds each field of a struct by name,
+///   binding each value of a `Vec` field and NULL for an `Option` that is `None`.
+///
+/// * `types::value::to_bind_values()`, with the `composer-serde` feature, binds the fields of any
+///   `Serialize` struct or map by name, binding each value of a sequence, a `Vec<u8>` as one blob,
+///   and naming the fields of a nested struct as `page.limit`.
+///
+/// * `ComposeRequest::query()`, with the `composer-serde` feature, executes the request and
+///   deserializes each row into any `Deserialize` type by column name, reading NULL as `None`.
+///
+/// * `compose_page()` composes a page of a statement together with a statement counting all of
+///   its rows, each with their own bind values.
+///
+/// * `compose_chunked()` splits the rows of a `:values()` across as many statements as are needed
+///   to stay within the driver's bind parameter limit.
+///
+/// * `PostgresComposer::set_array_bindings(true)` sends an array, such as a `Vec<i32>`, bound to a
+///   multi-valued binding in an `IN (...)` list as one parameter, composing `= ANY($1)` so the
+///   statement text stays the same size. Scalar values are still composed as the IN list.
+///
+/// * `PostgresComposer` binds a name with a single value once, reusing its `$n` wherever the name
+///   is repeated.
+///
+/// * `RusqliteComposer::set_named_placeholders(true)` composes `:name` placeholders, writing the
+///   dots of a nested name such as `page.limit` as `:page__limit`, and `compose_named()` returns
+///   the values paired with their names for rusqlite's `*_named()` calls.
+///
+/// * `composer::dialect::DialectComposer` composes for databases without a driver in this crate,
+///   such as SQL Server and Oracle, given their placeholder style, identifier quoting and
+///   pagination.
+///
+/// * `types::value::Value` implements the parameter traits of each driver, so one
+///   `BTreeMap<String, Vec<Value>>` can be handed to any composer with `composer::value_binds()`.
+///
+/// * `composer::shared::SharedComposer` takes owned values and mocks with each call and returns
+///   owned values, for when the bind values can't be borrowed for the length of a call.
+///
+/// * Project specific commands can be added by registering a `composer::MacroHandler` with
+///   `insert_macro()`, returning the sql to compose in their place along with any values they bind.
+///   `expand_macros()` collects those values to bind along with the request's own.
+///
+/// * Composers hold no values between calls: compose() takes the bind values and mocks of each
+///   call, so one composer can be stored in a struct or shared between threads and reused for the
+///   same statement with different sets of values, getting SQL that looks quite different due to
+///   the difference in number of placeholders and size/shape of the bind values returned as well.
 
 #[macro_use]
 extern crate error_chain;
@@ -1,10 +1,10 @@
 use crate::types::{ParsedItem, ParsedSpan, Position, Span, Sql, SqlBinding,
-                   SqlComposition, SqlCompositionAlias, SqlDbObject, SqlEnding, SqlKeyword,
-                   SqlLiteral};
+                   SqlComposition, SqlCompositionAlias, SqlConditional, SqlDbObject, SqlEnding,
+                   SqlIdentifier, SqlKeyword, SqlLiteral, SqlOrderBy, SqlPagination, SqlPredicate};
 
 use nom::{
-    IResult,
-    character::complete::multispace0
+    IResult, Slice,
+    character::complete::{multispace0, multispace1}
 };
 
 #[cfg(feature = "composer-serde")]
@@ -34,14 +34,7 @@ named!(
     _parse_template(Span) -> ParsedItem<SqlComposition>,
     dbg_dmp!(
     fold_many1!(
-            alt!(
-                complete!(do_parse!(position!() >> e: parse_sql_end >> (vec![Sql::Ending(e)])))
-                | complete!(do_parse!(position!() >> b: bindvar >> (vec![Sql::Binding(b)])))
-                | complete!(do_parse!(position!() >> sc: parse_composer_macro >> (vec![Sql::Composition((ParsedItem::from_span(sc.0, Span::new(""), None).expect("expected to make a Span in sc _parse_template"), sc.1))])))
-                | complete!(do_parse!(position!() >> dbo: db_object >> (vec![Sql::Keyword(dbo.0), Sql::DbObject(dbo.1)])))
-                | complete!(do_parse!(position!() >> k: keyword >> (vec![Sql::Keyword(k)])))
-                | complete!(do_parse!(position!() >> s: parse_sql >> (vec![Sql::Literal(s)]))
-            )),
+        template_item,
         ParsedItem::from_span(SqlComposition::default(), Span::new(""), None).expect("expected to make a Span in _parse_template parser"),
         |mut acc: ParsedItem<SqlComposition>, items: Vec<Sql>| {
             for item in items {
@@ -59,6 +52,13 @@ named!(
 
                         acc.item.push_sql(Sql::Composition((sc, aliases))).unwrap();
                     }
+                    Sql::Binding(mut b) => {
+                        if b.item.columns == Some(vec![]) {
+                            b.item.columns = acc.item.infer_row_columns();
+                        }
+
+                        acc.item.push_sql(Sql::Binding(b)).unwrap();
+                    }
                     _ => {
                         acc.item.push_sql(item).unwrap();
                     }
@@ -70,6 +70,27 @@ named!(
     ))
 );
 
+named!(
+    template_item(Span) -> Vec<Sql>,
+    alt!(
+        complete!(do_parse!(position!() >> e: parse_sql_end >> (vec![Sql::Ending(e)])))
+        | complete!(do_parse!(position!() >> b: bindvar >> (vec![Sql::Binding(b)])))
+        | complete!(do_parse!(position!() >> b: bind_rows >> (vec![Sql::Binding(b)])))
+        | complete!(do_parse!(position!() >> v: parse_values_macro >> (vec![Sql::Keyword(v.0), Sql::Binding(v.1)])))
+        | complete!(do_parse!(position!() >> c: parse_conditional >> (vec![Sql::Conditional(c)])))
+        | complete!(do_parse!(position!() >> p: parse_predicate >> (vec![Sql::Predicate(p)])))
+        | complete!(do_parse!(position!() >> i: parse_identifier >> (vec![Sql::Identifier(i)])))
+        | complete!(do_parse!(position!() >> o: parse_order_by >> (vec![Sql::OrderBy(o)])))
+        | complete!(do_parse!(position!() >> p: parse_paginate >> (vec![Sql::Pagination(p)])))
+        | complete!(do_parse!(position!() >> p: parse_paginate_after >> (vec![Sql::Pagination(p)])))
+        | complete!(do_parse!(position!() >> sc: parse_with_macro >> (vec![Sql::Composition((ParsedItem::from_span(sc.0, Span::new(""), None).expect("expected to make a Span in sc _parse_template"), sc.1))])))
+        | complete!(do_parse!(position!() >> sc: parse_composer_macro >> (vec![Sql::Composition((ParsedItem::from_span(sc.0, Span::new(""), None).expect("expected to make a Span in sc _parse_template"), sc.1))])))
+        | complete!(do_parse!(position!() >> dbo: db_object >> (vec![Sql::Keyword(dbo.0), Sql::DbObject(dbo.1)])))
+        | complete!(do_parse!(position!() >> k: keyword >> (vec![Sql::Keyword(k)])))
+        | complete!(do_parse!(position!() >> s: parse_sql >> (vec![Sql::Literal(s)])))
+    )
+);
+
 pub fn parse_template(
     span: Span,
     alias: Option<SqlCompositionAlias>,
@@ -110,6 +131,7 @@ named!(parse_composer_macro(Span) -> (SqlComposition, Vec<SqlCompositionAlias>),
                multispace0 >>
                columns: opt!(column_list) >>
                multispace0 >>
+               opt!(complete!(terminated!(tag_no_case!("of"), multispace1))) >>
                position!() >>
                of: of_list >>
                tag!(")") >>
@@ -130,6 +152,327 @@ named!(parse_composer_macro(Span) -> (SqlComposition, Vec<SqlCompositionAlias>),
        ))
 );
 
+// :with(name of path[, name of path...])
+named!(parse_with_macro(Span) -> (SqlComposition, Vec<SqlCompositionAlias>),
+       complete!(do_parse!(
+               tag!(":") >>
+               position!() >>
+               command: tag_no_case!("with") >>
+               tag!("(") >>
+               multispace0 >>
+               ctes: separated_nonempty_list!(
+                   do_parse!(multispace0 >> tag!(",") >> multispace0 >> ()),
+                   with_cte
+               ) >>
+               multispace0 >>
+               tag!(")") >>
+               ({
+                 let (names, of) = ctes.into_iter().unzip();
+
+                 let mut sc = SqlComposition {
+                     command: Some(ParsedItem::from_span(command.fragment.to_lowercase(), command, None).expect("invalid parsed item came from parser parse_with_macro")),
+                     columns: Some(names),
+                     of,
+                     ..Default::default()
+                 };
+
+                 sc.update_aliases().expect("expected to update aliases");
+
+                 (sc, vec![])
+               })
+       ))
+);
+
+named!(
+    with_cte(Span) -> (ParsedItem<String>, ParsedItem<SqlCompositionAlias>),
+    do_parse!(
+        position!() >>
+        name: take_while_name_char >>
+        multispace1 >>
+        tag_no_case!("of") >>
+        multispace1 >>
+        of: of_item >>
+        ({
+            let name = ParsedItem::from_span(name.fragment.to_string(), name, None)
+                .expect("unable to build ParsedItem of String from with_cte parser");
+
+            (name, of)
+        })
+    )
+);
+
+// :if(name) ... [:else ...] :endif
+named!(
+    parse_conditional(Span) -> ParsedItem<SqlConditional>,
+    complete!(do_parse!(
+        tag_no_case!(":if(") >>
+        multispace0 >>
+        position!() >>
+        name: identifier_name >>
+        multispace0 >>
+        tag!(")") >>
+        multispace0 >>
+        sql: conditional_branch >>
+        else_sql: opt!(complete!(preceded!(
+            terminated!(tag_no_case!(":else"), multispace0),
+            conditional_branch
+        ))) >>
+        tag_no_case!(":endif") >>
+        multispace0 >>
+        (
+            ParsedItem::from_span(
+                SqlConditional::new(
+                    name.fragment.to_string(),
+                    sql,
+                    else_sql.unwrap_or_default(),
+                ).expect("SqlConditional::new() failed unexpectedly from parse_conditional parser"),
+                name,
+                None
+            ).expect("expected Ok from ParsedItem::from_span in parse_conditional parser")
+        )
+    ))
+);
+
+named!(
+    conditional_branch(Span) -> Vec<Sql>,
+    fold_many0!(
+        preceded!(
+            not!(peek!(alt!(tag_no_case!(":else") | tag_no_case!(":endif")))),
+            template_item
+        ),
+        vec![],
+        |mut acc: Vec<Sql>, items: Vec<Sql>| {
+            acc.extend(items);
+            acc
+        }
+    )
+);
+
+// :ident(name ALLOW a, b, c)
+named!(
+    parse_identifier(Span) -> ParsedItem<SqlIdentifier>,
+    complete!(do_parse!(
+        tag_no_case!(":ident(") >>
+        multispace0 >>
+        position!() >>
+        name: identifier_name >>
+        multispace1 >>
+        tag_no_case!("allow") >>
+        multispace1 >>
+        allow: separated_nonempty_list!(
+            do_parse!(multispace0 >> tag!(",") >> multispace0 >> ()),
+            identifier_name
+        ) >>
+        multispace0 >>
+        tag!(")") >>
+        multispace0 >>
+        (
+            ParsedItem::from_span(
+                SqlIdentifier::new(
+                    name.fragment.to_string(),
+                    allow.iter().map(|a| a.fragment.to_string()).collect(),
+                ).expect("SqlIdentifier::new() failed unexpectedly from parse_identifier parser"),
+                name,
+                None
+            ).expect("expected Ok from ParsedItem::from_span in parse_identifier parser")
+        )
+    ))
+);
+
+// :order_by(name ALLOW a, b, c [DEFAULT a DESC, b])
+named!(
+    parse_order_by(Span) -> ParsedItem<SqlOrderBy>,
+    complete!(do_parse!(
+        tag_no_case!(":order_by(") >>
+        multispace0 >>
+        position!() >>
+        name: identifier_name >>
+        multispace1 >>
+        tag_no_case!("allow") >>
+        multispace1 >>
+        allow: separated_nonempty_list!(
+            do_parse!(multispace0 >> tag!(",") >> multispace0 >> ()),
+            identifier_name
+        ) >>
+        multispace0 >>
+        default: opt!(complete!(preceded!(
+            terminated!(tag_no_case!("default"), multispace1),
+            separated_nonempty_list!(
+                do_parse!(multispace0 >> tag!(",") >> multispace0 >> ()),
+                order_term
+            )
+        ))) >>
+        multispace0 >>
+        tag!(")") >>
+        multispace0 >>
+        (
+            ParsedItem::from_span(
+                SqlOrderBy::new(
+                    name.fragment.to_string(),
+                    allow.iter().map(|a| a.fragment.to_string()).collect(),
+                    default.unwrap_or_default(),
+                ).expect("SqlOrderBy::new() failed unexpectedly from parse_order_by parser"),
+                name,
+                None
+            ).expect("expected Ok from ParsedItem::from_span in parse_order_by parser")
+        )
+    ))
+);
+
+// :paginate(limit[, offset])
+named!(
+    parse_paginate(Span) -> ParsedItem<SqlPagination>,
+    complete!(do_parse!(
+        tag_no_case!(":paginate(") >>
+        multispace0 >>
+        position!() >>
+        limit: identifier_name >>
+        offset: opt!(complete!(preceded!(
+            do_parse!(multispace0 >> tag!(",") >> multispace0 >> ()),
+            identifier_name
+        ))) >>
+        multispace0 >>
+        tag!(")") >>
+        multispace0 >>
+        (
+            ParsedItem::from_span(
+                SqlPagination::new(
+                    limit.fragment.to_string(),
+                    offset.map(|o| o.fragment.to_string()),
+                ).expect("SqlPagination::new() failed unexpectedly from parse_paginate parser"),
+                limit,
+                None
+            ).expect("expected Ok from ParsedItem::from_span in parse_paginate parser")
+        )
+    ))
+);
+
+// :paginate_after(column, cursor[, limit])
+named!(
+    parse_paginate_after(Span) -> ParsedItem<SqlPagination>,
+    complete!(do_parse!(
+        tag_no_case!(":paginate_after(") >>
+        multispace0 >>
+        position!() >>
+        column: identifier_name >>
+        multispace0 >>
+        tag!(",") >>
+        multispace0 >>
+        cursor: identifier_name >>
+        limit: opt!(complete!(preceded!(
+            do_parse!(multispace0 >> tag!(",") >> multispace0 >> ()),
+            identifier_name
+        ))) >>
+        multispace0 >>
+        tag!(")") >>
+        multispace0 >>
+        (
+            ParsedItem::from_span(
+                SqlPagination::after(
+                    column.fragment.to_string(),
+                    cursor.fragment.to_string(),
+                    limit.map(|l| l.fragment.to_string()),
+                ).expect("SqlPagination::after() failed unexpectedly from parse_paginate_after parser"),
+                column,
+                None
+            ).expect("expected Ok from ParsedItem::from_span in parse_paginate_after parser")
+        )
+    ))
+);
+
+// column [ASC|DESC]
+named!(
+    order_term(Span) -> String,
+    do_parse!(
+        column: identifier_name >>
+        direction: opt!(complete!(preceded!(
+            multispace1,
+            alt!(tag_no_case!("asc") | tag_no_case!("desc"))
+        ))) >>
+        ({
+            match direction {
+                Some(d) => format!("{} {}", column.fragment, d.fragment.to_uppercase()),
+                None => column.fragment.to_string(),
+            }
+        })
+    )
+);
+
+// a name that may be qualified, such as schema.table or the bound name page.limit
+named!(identifier_name(Span) -> Span,
+    take_while1!(|c| {
+        match c {
+            'a'..='z' => true,
+            'A'..='Z' => true,
+            '0'..='9' => true,
+            '_' | '.' => true,
+            _ => false,
+        }
+    })
+);
+
+// :and(sql) or :or(sql)
+fn parse_predicate(span: Span) -> IResult<Span, ParsedItem<SqlPredicate>> {
+    let (span, _) = tag!(span, ":")?;
+    let (span, conjunction) = alt!(span, tag_no_case!("and") | tag_no_case!("or"))?;
+    let (span, _) = tag!(span, "(")?;
+    let (span, _) = multispace0(span)?;
+    let (span, body) = predicate_body(span)?;
+    let (span, _) = tag!(span, ")")?;
+    let (span, _) = multispace0(span)?;
+    let (_, sql) = predicate_sql(body)?;
+
+    Ok((
+        span,
+        ParsedItem::from_span(
+            SqlPredicate::new(conjunction.fragment.to_uppercase(), sql)
+                .expect("SqlPredicate::new() failed unexpectedly from parse_predicate parser"),
+            conjunction,
+            None,
+        )
+        .expect("expected Ok from ParsedItem::from_span in parse_predicate parser"),
+    ))
+}
+
+// everything up to the ) closing the predicate, skipping over nested parens and quoted text
+fn predicate_body(span: Span) -> IResult<Span, Span> {
+    let mut depth = 0;
+    let mut quoted = false;
+
+    for (i, c) in span.fragment.char_indices() {
+        match c {
+            '\'' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => {
+                if depth == 0 {
+                    return Ok((span.slice(i..), span.slice(..i)));
+                }
+
+                depth -= 1;
+            }
+            _ => {}
+        }
+    }
+
+    Err(nom::Err::Error(error_position!(span, nom::error::ErrorKind::TakeUntil)))
+}
+
+named!(
+    predicate_sql(Span) -> Vec<Sql>,
+    do_parse!(
+        sql: fold_many0!(
+            template_item,
+            vec![],
+            |mut acc: Vec<Sql>, items: Vec<Sql>| {
+                acc.extend(items);
+                acc
+            }
+        ) >>
+        eof!() >>
+        (sql)
+    )
+);
+
 named!(
     command_distinct_arg(Span) -> Option<ParsedItem<bool>>,
     do_parse!(
@@ -321,32 +664,35 @@ named!(
 named!(
     of_list(Span) -> Vec<ParsedItem<SqlCompositionAlias>>,
     many1!(terminated!(
-        do_parse!(
-            position!() >>
-            of_name: take_while1!(|u| {
-                let c = u as char;
-
-                match c {
-                    'a'..='z' => true,
-                    'A'..='Z' => true,
-                    '0'..='9' => true,
-                    '-' | '_' => true,
-                    '.' | '/' | '\\' => true,
-                    _ => false,
-                }
-            }) >> ({
-                //TODO: clean this up properly
-                let alias = SqlCompositionAlias::from_span(of_name).expect("expected alias from_span in of_list");
-
-                ParsedItem::from_span(alias, of_name, None).expect("Unable to create parsed item in of_list parser")
-            })
-        ),
+        of_item,
         opt!(do_parse!(
             multispace0 >> tag!(",") >> multispace0 >> ()
         ))
     ))
 );
 
+named!(
+    of_item(Span) -> ParsedItem<SqlCompositionAlias>,
+    do_parse!(
+        position!() >>
+        of_name: take_while1!(|c| {
+            match c {
+                'a'..='z' => true,
+                'A'..='Z' => true,
+                '0'..='9' => true,
+                '-' | '_' => true,
+                '.' | '/' | '\\' => true,
+                _ => false,
+            }
+        }) >> ({
+            //TODO: clean this up properly
+            let alias = SqlCompositionAlias::from_span(of_name).expect("expected alias from_span in of_list");
+
+            ParsedItem::from_span(alias, of_name, None).expect("Unable to create parsed item in of_list parser")
+        })
+    )
+);
+
 named!(
     _parse_macro_include_alias(Span) -> Span,
     dbg!(
@@ -421,7 +767,7 @@ named!(bindvar(Span) -> ParsedItem<SqlBinding>,
                tag_no_case!(":bind(") >>
                multispace0 >>
                position!() >>
-               bindvar_name: take_while_name_char >>
+               bindvar_name: identifier_name >>
                multispace0 >>
                position!() >>
                expecting: opt!(bindvar_expecting) >>
@@ -430,7 +776,7 @@ named!(bindvar(Span) -> ParsedItem<SqlBinding>,
                multispace0 >>
                tag!(")") >>
                multispace0 >>
-               end_quote: opt!(tag!("'")) >>
+               end_quote: opt!(complete!(tag!("'"))) >>
                ({
                    let min = expecting.and_then(|m| m.0);
                    let max = expecting.and_then(|m| m.1);
@@ -459,19 +805,109 @@ named!(bindvar(Span) -> ParsedItem<SqlBinding>,
        ))
 );
 
+// name COLUMNS a, b [EXPECTING (i|MIN i|MAX i|MIN i MAX i)]
+named!(bind_rows(Span) -> ParsedItem<SqlBinding>,
+       complete!(do_parse!(
+               tag_no_case!(":bind_rows(") >>
+               multispace0 >>
+               position!() >>
+               name: identifier_name >>
+               multispace1 >>
+               tag_no_case!("columns") >>
+               multispace1 >>
+               columns: separated_nonempty_list!(
+                   do_parse!(multispace0 >> tag!(",") >> multispace0 >> ()),
+                   take_while_name_char
+               ) >>
+               multispace0 >>
+               expecting: opt!(bindvar_expecting) >>
+               multispace0 >>
+               tag!(")") >>
+               multispace0 >>
+               ({
+                   let min = expecting.and_then(|m| m.0);
+                   let max = expecting.and_then(|m| m.1);
+
+                   ParsedItem::from_span(
+                       SqlBinding::rows(
+                           name.fragment.to_string(),
+                           columns.iter().map(|c| c.fragment.to_string()).collect(),
+                           min,
+                           max,
+                       ).expect("SqlBinding::rows() failed unexpectedly from bind_rows parser"),
+                       name,
+                       None
+                   ).expect("expected Ok from ParsedItem::from_span in bind_rows parser")
+               })
+       ))
+);
+
+// :values(name [COLUMNS a, b] [EXPECTING (i|MIN i|MAX i|MIN i MAX i)])
+named!(parse_values_macro(Span) -> (ParsedItem<SqlKeyword>, ParsedItem<SqlBinding>),
+       complete!(do_parse!(
+               tag!(":") >>
+               keyword: tag_no_case!("values") >>
+               tag!("(") >>
+               multispace0 >>
+               position!() >>
+               name: identifier_name >>
+               multispace0 >>
+               columns: opt!(complete!(preceded!(
+                   terminated!(tag_no_case!("columns"), multispace1),
+                   separated_nonempty_list!(
+                       do_parse!(multispace0 >> tag!(",") >> multispace0 >> ()),
+                       take_while_name_char
+                   )
+               ))) >>
+               multispace0 >>
+               expecting: opt!(bindvar_expecting) >>
+               multispace0 >>
+               tag!(")") >>
+               multispace0 >>
+               ({
+                   let min = expecting.and_then(|m| m.0);
+                   let max = expecting.and_then(|m| m.1);
+
+                   // an empty column list is filled in from the sql before the macro
+                   let columns = columns
+                       .map(|c| c.iter().map(|c| c.fragment.to_string()).collect())
+                       .unwrap_or_default();
+
+                   let k = ParsedItem::from_span(
+                       SqlKeyword::new(keyword.fragment.to_uppercase()).expect("SqlKeyword::new() failed unexpectedly from parse_values_macro parser"),
+                       keyword,
+                       None
+                   ).expect("expected Ok from ParsedItem::from_span in parse_values_macro parser");
+
+                   let b = ParsedItem::from_span(
+                       SqlBinding::rows(
+                           name.fragment.to_string(),
+                           columns,
+                           min,
+                           max,
+                       ).expect("SqlBinding::rows() failed unexpectedly from parse_values_macro parser"),
+                       name,
+                       None
+                   ).expect("expected Ok from ParsedItem::from_span in parse_values_macro parser");
+
+                   (k, b)
+               })
+       ))
+);
+
 named!(
     parse_sql(Span) -> ParsedItem<SqlLiteral>,
     do_parse!(
         pos: position!() >>
         parsed: fold_many1!(
-            do_parse!(
+            complete!(do_parse!(
                 not!(peek!(tag!(":"))) >>
                 not!(peek!(tag!(";"))) >>
                 not!(peek!(tag!("'"))) >>
                 not!(peek!(db_object_pre_sql)) >>
                 literal: take!(1) >>
                 (literal)
-            ),
+            )),
             ParsedItem::from_span(SqlLiteral::default(), Span::new(""), None).expect("expected to make a Span in parse_sql parser"),
             |mut acc: ParsedItem<SqlLiteral>, item: Span| {
                 acc.item.value.push_str(&item.fragment);
@@ -630,7 +1066,7 @@ named!(
 named!(
     bind_value_kv_pair(Span) -> (Span, Vec<SerdeValue>),
     do_parse!(
-        key: take_while_name_char >>
+        key: identifier_name >>
         multispace0 >>
         tag!(":") >>
         multispace0 >>
@@ -706,8 +1142,10 @@ named!(
 
 #[cfg(test)]
 mod tests {
-    use super::{bindvar, bindvar_expecting, column_list, db_object, db_object_alias_sql,
-                parse_composer_macro, parse_sql, parse_sql_end, parse_template};
+    use super::{bind_rows, bindvar, bindvar_expecting, column_list, db_object, db_object_alias_sql,
+                parse_composer_macro, parse_conditional, parse_identifier, parse_order_by,
+                parse_paginate, parse_paginate_after, parse_predicate, parse_sql, parse_sql_end,
+                parse_template, parse_values_macro, parse_with_macro};
 
     #[cfg(feature = "composer-serde")]
     use super::{
@@ -721,7 +1159,7 @@ mod tests {
     };
 
     use crate::types::{ParsedItem, Span, Sql, SqlComposition, SqlCompositionAlias, SqlDbObject,
-                       SqlEnding, SqlLiteral};
+                       SqlBinding, SqlEnding, SqlLiteral, SqlPagination};
 
     #[cfg(feature = "composer-serde")]
     use crate::types::SerdeValue;
@@ -908,6 +1346,22 @@ mod tests {
         assert_eq!(span, expected_span, "spans match");
     }
 
+    #[test]
+    fn it_parses_dotted_bindvar() {
+        let input = ":bind(page.limit)blah blah blah";
+
+        let out = bindvar(Span::new(input.into())).expect("expected Ok from bindvar");
+
+        let expected_span = build_span(Some(1), Some(17), "blah blah blah");
+        let expected_item =
+            build_parsed_binding_item("page.limit", None, None, false, None, Some(6), "page.limit");
+
+        let (span, item) = out;
+
+        assert_eq!(item, expected_item, "items match");
+        assert_eq!(span, expected_span, "spans match");
+    }
+
     #[test]
     fn it_parses_bindvar_expecting_only_min() {
         let input = "EXPECTING MIN 1blah blah blah";
@@ -1167,6 +1621,245 @@ mod tests {
         assert_eq!(comp, expected);
     }
 
+    #[test]
+    fn test_parse_composer_macro_without_columns() {
+        let sql_str = ":soft_deleted(of src/tests/simple-template.tql);";
+
+        let (span, (comp, _aliases)) = parse_composer_macro(Span::new(sql_str.into()))
+            .expect("expected Ok from parse_composer_macro");
+
+        assert_eq!(span.fragment, ";", "remaining");
+        assert_eq!(comp.command.unwrap().item, "soft_deleted", "command");
+        assert_eq!(comp.columns, None, "columns");
+        assert_eq!(
+            comp.of,
+            vec![build_parsed_item(
+                SqlCompositionAlias::Path("src/tests/simple-template.tql".into()),
+                None,
+                Some(17),
+                "src/tests/simple-template.tql",
+            )],
+            "of"
+        );
+    }
+
+    #[test]
+    fn test_parse_conditional() {
+        let sql_str = ":if(a) AND col_1 = :bind(a) :else AND col_1 IS NULL :endif;";
+
+        let (span, cond) = parse_conditional(Span::new(sql_str.into()))
+            .expect("expected Ok from parse_conditional");
+
+        assert_eq!(span.fragment, ";", "remaining");
+        assert_eq!(cond.item.name, "a", "name");
+
+        let branch = |sql: &Vec<Sql>| sql.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+
+        assert_eq!(branch(&cond.item.sql), vec!["AND col_1 =", "a"], "sql");
+        assert_eq!(branch(&cond.item.else_sql), vec!["AND col_1 IS NULL"], "else_sql");
+    }
+
+    #[test]
+    fn test_parse_nested_conditional() {
+        let sql_str = "SELECT * FROM t AS t WHERE 1 = 1 :if(a) AND a = :bind(a) :if(b) AND b = :bind(b) :endif :endif;";
+
+        let (span, comp) = parse_template(Span::new(sql_str.into()), None)
+            .expect("expected Ok from parse_template");
+
+        assert_eq!(span.fragment, "", "remaining");
+
+        let outer = match &comp.item.sql[6] {
+            Sql::Conditional(c) => c,
+            s => panic!("expected a conditional, found {:?}", s),
+        };
+
+        assert_eq!(outer.item.name, "a", "outer name");
+        assert_eq!(outer.item.else_sql, vec![], "outer else_sql");
+
+        match &outer.item.sql[2] {
+            Sql::Conditional(c) => assert_eq!(c.item.name, "b", "inner name"),
+            s => panic!("expected a conditional, found {:?}", s),
+        }
+
+        assert_eq!(comp.item.sql.len(), 8, "items");
+    }
+
+    #[test]
+    fn test_parse_predicate() {
+        let sql_str = ":AND(col_1 IN (:bind(a EXPECTING MIN 1)) OR col_2 = ':bind(b)') AND 1 = 1;";
+
+        let (span, predicate) = parse_predicate(Span::new(sql_str.into()))
+            .expect("expected Ok from parse_predicate");
+
+        assert_eq!(span.fragment, "AND 1 = 1;", "remaining");
+        assert_eq!(predicate.item.conjunction, "AND", "conjunction");
+        assert_eq!(predicate.item.binding_names(), vec!["a", "b"], "binding names");
+        assert_eq!(
+            predicate.item.sql.iter().map(|s| s.to_string()).collect::<Vec<String>>(),
+            vec!["col_1 IN (", "a", ") OR col_2 =", "b"],
+            "sql"
+        );
+    }
+
+    #[test]
+    fn test_parse_identifier() {
+        let sql_str = ":ident(sort ALLOW col_1, main.col_2 ,col_3) DESC;";
+
+        let (span, ident) = parse_identifier(Span::new(sql_str.into()))
+            .expect("expected Ok from parse_identifier");
+
+        assert_eq!(span.fragment, "DESC;", "remaining");
+        assert_eq!(ident.item.name, "sort", "name");
+        assert_eq!(ident.item.allow, vec!["col_1", "main.col_2", "col_3"], "allow");
+
+        assert!(
+            parse_identifier(Span::new(":ident(sort)".into())).is_err(),
+            "an allow list is required"
+        );
+    }
+
+    #[test]
+    fn test_parse_order_by() {
+        let sql_str = ":order_by(sort ALLOW created_at, name DEFAULT created_at desc, name) LIMIT 10;";
+
+        let (span, order) = parse_order_by(Span::new(sql_str.into()))
+            .expect("expected Ok from parse_order_by");
+
+        assert_eq!(span.fragment, "LIMIT 10;", "remaining");
+        assert_eq!(order.item.name, "sort", "name");
+        assert_eq!(order.item.allow, vec!["created_at", "name"], "allow");
+        assert_eq!(order.item.default, vec!["created_at DESC", "name"], "default");
+
+        let (span, order) = parse_order_by(Span::new(":order_by(sort ALLOW name);".into()))
+            .expect("expected Ok from parse_order_by without a default");
+
+        assert_eq!(span.fragment, ";", "remaining");
+        assert_eq!(order.item.default, Vec::<String>::new(), "default");
+    }
+
+    #[test]
+    fn test_parse_paginate() {
+        let (span, page) = parse_paginate(Span::new(":paginate(limit, offset);".into()))
+            .expect("expected Ok from parse_paginate");
+
+        assert_eq!(span.fragment, ";", "remaining");
+        assert_eq!(
+            page.item,
+            SqlPagination::new("limit".into(), Some("offset".into())).unwrap(),
+            "limit and offset"
+        );
+
+        let (span, page) = parse_paginate(Span::new(":paginate( limit ) ;".into()))
+            .expect("expected Ok from parse_paginate without an offset");
+
+        assert_eq!(span.fragment, ";", "remaining");
+        assert_eq!(page.item, SqlPagination::new("limit".into(), None).unwrap(), "limit only");
+
+        let (span, page) = parse_paginate_after(Span::new(":paginate_after(main.id, after, limit);".into()))
+            .expect("expected Ok from parse_paginate_after");
+
+        assert_eq!(span.fragment, ";", "remaining");
+        assert_eq!(
+            page.item,
+            SqlPagination::after("main.id".into(), "after".into(), Some("limit".into())).unwrap(),
+            "keyset"
+        );
+        assert_eq!(page.item.to_string(), ":paginate_after(main.id, after, limit)", "display");
+    }
+
+    #[test]
+    fn test_bind_rows() {
+        let (span, binding) = bind_rows(Span::new(":bind_rows(pairs COLUMNS tenant_id, user_id EXPECTING MAX 10));".into()))
+            .expect("expected Ok from bind_rows");
+
+        assert_eq!(span.fragment, ");", "remaining");
+        assert_eq!(
+            binding.item,
+            SqlBinding::rows(
+                "pairs".into(),
+                vec!["tenant_id".into(), "user_id".into()],
+                None,
+                Some(10)
+            )
+            .unwrap(),
+            "binding"
+        );
+
+        assert!(
+            bind_rows(Span::new(":bind_rows(pairs)".into())).is_err(),
+            "columns are required"
+        );
+    }
+
+    #[test]
+    fn test_parse_values_macro() {
+        let (_span, stmt) = parse_template(Span::new("INSERT INTO t (a, b) :values(rows);".into()), None)
+            .expect("expected Ok from parse_template");
+
+        match &stmt.item.sql[2] {
+            Sql::Keyword(k) => assert_eq!(k.item.value, "VALUES", "keyword"),
+            s => panic!("expected a keyword, found {:?}", s),
+        }
+
+        match &stmt.item.sql[3] {
+            Sql::Binding(b) => assert_eq!(
+                b.item,
+                SqlBinding::rows("rows".into(), vec!["a".into(), "b".into()], None, None).unwrap(),
+                "columns inferred from the insert"
+            ),
+            s => panic!("expected a binding, found {:?}", s),
+        }
+
+        let (span, (_keyword, binding)) = parse_values_macro(Span::new(":values(rows COLUMNS x, y, z EXPECTING MAX 5);".into()))
+            .expect("expected Ok from parse_values_macro");
+
+        assert_eq!(span.fragment, ";", "remaining");
+        assert_eq!(
+            binding.item,
+            SqlBinding::rows(
+                "rows".into(),
+                vec!["x".into(), "y".into(), "z".into()],
+                None,
+                Some(5)
+            )
+            .unwrap(),
+            "explicit columns"
+        );
+    }
+
+    #[test]
+    fn test_parse_with_macro() {
+        let sql_str = ":with(simple of src/tests/simple-template.tql);";
+
+        let comp = parse_with_macro(Span::new(sql_str.into()));
+
+        let expected = Ok((
+            Span {
+                offset:   46,
+                line:     1,
+                fragment: ";".into(),
+                extra: (),
+            },
+            (
+                SqlComposition {
+                    command: Some(build_parsed_string("with", None, Some(1), "with")),
+                    columns: Some(vec![build_parsed_string("simple", None, Some(6), "simple")]),
+                    of: vec![build_parsed_item(
+                        SqlCompositionAlias::Path("src/tests/simple-template.tql".into()),
+                        None,
+                        Some(16),
+                        "src/tests/simple-template.tql",
+                    )],
+                    aliases: simple_alias_hash(),
+                    ..Default::default()
+                },
+                vec![],
+            ),
+        ));
+
+        assert_eq!(comp, expected);
+    }
+
     #[test]
     fn test_simple_composed_composer() {
         let sql_str = ":count(src/tests/simple-template.tql);";
@@ -202,6 +202,40 @@ impl<T: fmt::Display + Debug + Default + PartialEq + Clone> fmt::Display for Par
 //            :except([distinct] [column1, column2 of] t1.sql, t2.tql)
 //            :intercept([distinct] [column1, column2 of] t1.sql, t2.tql)
 //            :union([all|distinct] [column1, column2 of] t1.sql, t2.tql)
+//            :with(name1 of t1.tql, name2 of t2.tql)
+//            :exists(t1.tql)
+//            :not_exists(t1.tql)
+//
+//binding     - :bind(name [EXPECTING ...] [NULL]) :bind_rows(name COLUMNS a, b [EXPECTING ...])
+//----------------------------------|-------------------------------------------
+// bind_rows takes the values of each row one after the other and expects rows, not values
+//
+//values      - :values(name [COLUMNS a, b] [EXPECTING ...])
+//----------------------------------|-------------------------------------------
+// VALUES followed by a bind_rows, the columns default to the column list before the macro
+//
+//conditional - :if(name) ... [:else ...] :endif
+//----------------------------------|-------------------------------------------
+// the first block is used when name has at least one value bound, otherwise the :else block
+//
+//predicate - :and(sql) :or(sql)
+//----------------------------------|-------------------------------------------
+// sql and its conjunction are only used when every binding in sql has values, the conjunction is
+// dropped directly after WHERE and a WHERE left without any conditions is removed
+//
+//identifier - :ident(name ALLOW a, b, c)
+//----------------------------------|-------------------------------------------
+// the value bound to name must be one of the allowed identifiers and is quoted for the dialect
+//
+//order by - :order_by(name ALLOW a, b, c [DEFAULT a DESC, b])
+//----------------------------------|-------------------------------------------
+// each "column [ASC|DESC]" bound to name must use an allowed column, the default is used when
+// nothing is bound and the clause is left out when there is neither
+//
+//pagination - :paginate(limit[, offset]) :paginate_after(column, cursor[, limit])
+//----------------------------------|-------------------------------------------
+// limit, offset and cursor name bindings, each left out when nothing is bound to it;
+// paginate_after adds "column > cursor" to the WHERE clause and orders by column
 
 #[derive(Debug, Default, PartialEq, Clone)]
 pub struct SqlComposition {
@@ -269,6 +303,55 @@ impl SqlComposition {
         }
     }
 
+    pub fn infer_row_columns(&self) -> Option<Vec<String>> {
+        //! Returns the names in the last parenthesized list of the literal before the end of sql,
+        //! skipping keywords, such as the a and b of "INSERT INTO t (a, b) VALUES"
+        match self.sql.iter().rev().find(|s| !matches!(s, Sql::Keyword(_))) {
+            Some(Sql::Literal(l)) => {
+                let value = &l.item.value;
+
+                let start = value.rfind('(')?;
+                let end = start + value[start..].find(')')?;
+
+                let columns: Vec<String> = value[start + 1..end]
+                    .split(',')
+                    .map(|c| c.trim().to_string())
+                    .filter(|c| !c.is_empty())
+                    .collect();
+
+                if !columns.is_empty() {
+                    Some(columns)
+                }
+                else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    pub fn find_binding(&self, name: &str) -> Option<&SqlBinding> {
+        //! Finds the first binding named name, looking into sub compositions
+        for s in &self.sql {
+            let found = match s {
+                Sql::Binding(b) if b.item.name == name => Some(&b.item),
+                Sql::Composition((sc, _)) => sc.item.find_binding(name).or_else(|| {
+                    sc.item
+                        .aliases
+                        .values()
+                        .find_map(|a| a.item.find_binding(name))
+                }),
+                _ => None,
+            };
+
+            if found.is_some() {
+                return found;
+            }
+        }
+
+        None
+    }
+
     pub fn push_sql(&mut self, c: Sql) -> Result<()> {
         self.sql.push(c);
 
@@ -319,6 +402,30 @@ impl SqlComposition {
         )))
     }
 
+    pub fn push_generated_compose(
+        &mut self,
+        of: &ParsedItem<SqlCompositionAlias>,
+        aliases: &HashMap<SqlCompositionAlias, ParsedItem<SqlComposition>>,
+        command: Option<String>,
+    ) -> Result<()> {
+        let sc = SqlComposition {
+            command: Some(ParsedItem::generated("compose".into(), command.clone())?),
+            of: vec![of.clone()],
+            aliases: aliases.clone(),
+            ..Default::default()
+        };
+
+        self.push_sql(Sql::Composition((ParsedItem::generated(sc, command)?, vec![])))
+    }
+
+    pub fn push_generated_binding(
+        &mut self,
+        binding: SqlBinding,
+        command: Option<String>,
+    ) -> Result<()> {
+        self.push_sql(Sql::Binding(ParsedItem::generated(binding, command)?))
+    }
+
     pub fn push_generated_literal(&mut self, value: &str, command: Option<String>) -> Result<()> {
         self.push_sql(Sql::Literal(ParsedItem::generated(
             SqlLiteral {
@@ -384,6 +491,11 @@ pub enum Sql {
     Ending(ParsedItem<SqlEnding>),
     DbObject(ParsedItem<SqlDbObject>),
     Keyword(ParsedItem<SqlKeyword>),
+    Conditional(ParsedItem<SqlConditional>),
+    Predicate(ParsedItem<SqlPredicate>),
+    Identifier(ParsedItem<SqlIdentifier>),
+    OrderBy(ParsedItem<SqlOrderBy>),
+    Pagination(ParsedItem<SqlPagination>),
 }
 
 impl fmt::Display for Sql {
@@ -395,12 +507,196 @@ impl fmt::Display for Sql {
             Sql::Ending(e) => write!(f, "{}", e)?,
             Sql::DbObject(ft) => write!(f, "{}", ft)?,
             Sql::Keyword(k) => write!(f, "{}", k)?,
+            Sql::Conditional(c) => write!(f, "{}", c)?,
+            Sql::Predicate(p) => write!(f, "{}", p)?,
+            Sql::Identifier(i) => write!(f, "{}", i)?,
+            Sql::OrderBy(o) => write!(f, "{}", o)?,
+            Sql::Pagination(p) => write!(f, "{}", p)?,
         }
 
         write!(f, "")
     }
 }
 
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct SqlConditional {
+    pub name:     String,
+    pub sql:      Vec<Sql>,
+    pub else_sql: Vec<Sql>,
+}
+
+impl SqlConditional {
+    pub fn new(name: String, sql: Vec<Sql>, else_sql: Vec<Sql>) -> Result<Self> {
+        Ok(Self {
+            name,
+            sql,
+            else_sql,
+        })
+    }
+}
+
+impl fmt::Display for SqlConditional {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, ":if({})", self.name)?;
+
+        for s in &self.sql {
+            write!(f, " {}", s)?;
+        }
+
+        if !self.else_sql.is_empty() {
+            write!(f, " :else")?;
+
+            for s in &self.else_sql {
+                write!(f, " {}", s)?;
+            }
+        }
+
+        write!(f, " :endif")
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct SqlPredicate {
+    pub conjunction: String,
+    pub sql:         Vec<Sql>,
+}
+
+impl SqlPredicate {
+    pub fn new(conjunction: String, sql: Vec<Sql>) -> Result<Self> {
+        Ok(Self { conjunction, sql })
+    }
+
+    pub fn binding_names(&self) -> Vec<String> {
+        //! Returns the names of the bindings directly in this predicate
+        self.sql
+            .iter()
+            .filter_map(|s| match s {
+                Sql::Binding(b) => Some(b.item.name.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for SqlPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, ":{}(", self.conjunction.to_lowercase())?;
+
+        for (i, s) in self.sql.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+
+            write!(f, "{}", s)?;
+        }
+
+        write!(f, ")")
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct SqlIdentifier {
+    pub name:  String,
+    pub allow: Vec<String>,
+}
+
+impl SqlIdentifier {
+    pub fn new(name: String, allow: Vec<String>) -> Result<Self> {
+        Ok(Self { name, allow })
+    }
+
+    pub fn is_allowed(&self, ident: &str) -> bool {
+        self.allow.iter().any(|a| a == ident)
+    }
+}
+
+impl fmt::Display for SqlIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, ":ident({} ALLOW {})", self.name, self.allow.join(", "))
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct SqlOrderBy {
+    pub name:    String,
+    pub allow:   Vec<String>,
+    pub default: Vec<String>,
+}
+
+impl SqlOrderBy {
+    pub fn new(name: String, allow: Vec<String>, default: Vec<String>) -> Result<Self> {
+        Ok(Self {
+            name,
+            allow,
+            default,
+        })
+    }
+
+    pub fn is_allowed(&self, column: &str) -> bool {
+        self.allow.iter().any(|a| a == column)
+    }
+}
+
+impl fmt::Display for SqlOrderBy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, ":order_by({} ALLOW {}", self.name, self.allow.join(", "))?;
+
+        if !self.default.is_empty() {
+            write!(f, " DEFAULT {}", self.default.join(", "))?;
+        }
+
+        write!(f, ")")
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct SqlPagination {
+    pub limit:         Option<String>,
+    pub offset:        Option<String>,
+    pub cursor_column: Option<String>,
+    pub cursor:        Option<String>,
+}
+
+impl SqlPagination {
+    pub fn new(limit: String, offset: Option<String>) -> Result<Self> {
+        Ok(Self {
+            limit: Some(limit),
+            offset,
+            ..Default::default()
+        })
+    }
+
+    pub fn after(cursor_column: String, cursor: String, limit: Option<String>) -> Result<Self> {
+        Ok(Self {
+            limit,
+            cursor_column: Some(cursor_column),
+            cursor: Some(cursor),
+            ..Default::default()
+        })
+    }
+}
+
+impl fmt::Display for SqlPagination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let args = match (&self.cursor_column, &self.cursor) {
+            (Some(column), Some(cursor)) => {
+                write!(f, ":paginate_after(")?;
+
+                vec![Some(column), Some(cursor), self.limit.as_ref()]
+            }
+            _ => {
+                write!(f, ":paginate(")?;
+
+                vec![self.limit.as_ref(), self.offset.as_ref()]
+            }
+        };
+
+        let args: Vec<String> = args.into_iter().flatten().cloned().collect();
+
+        write!(f, "{})", args.join(", "))
+    }
+}
+
 #[derive(Debug, Default, PartialEq, Clone)]
 pub struct SqlEnding {
     pub value: String,
@@ -491,6 +787,7 @@ pub struct SqlBinding {
     pub min_values: Option<u32>,
     pub max_values: Option<u32>,
     pub nullable:   bool,
+    pub columns:    Option<Vec<String>>,
 }
 
 impl SqlBinding {
@@ -507,6 +804,23 @@ impl SqlBinding {
             max_values,
             quoted,
             nullable,
+            columns: None,
+        })
+    }
+
+    pub fn rows(
+        name: String,
+        columns: Vec<String>,
+        min_values: Option<u32>,
+        max_values: Option<u32>,
+    ) -> Result<Self> {
+        //! A binding of row values, with min_values and max_values counting rows
+        Ok(Self {
+            name,
+            min_values,
+            max_values,
+            columns: Some(columns),
+            ..Default::default()
         })
     }
 }
@@ -2,34 +2,166 @@ use super::Null;
 
 use chrono::prelude::*;
 
+use rust_decimal::Decimal;
+
+use uuid::Uuid;
+
+use std::collections::BTreeMap;
+
+#[cfg(feature = "derive")]
+pub use sql_composer_derive::BindValues;
+
+#[cfg(feature = "composer-serde")]
+pub mod ser;
+
+#[cfg(feature = "composer-serde")]
+pub use self::ser::to_bind_values;
+
 //borrowed from rusqlite's Value type
 #[derive(Clone, Debug, PartialEq)]
 pub enum Value {
     /// The value is a `NULL` value.
     Null,
+    /// The value is a boolean.
+    Bool(bool),
     /// The value is a signed integer.
     Integer(i64),
     /// The value is a floating point number.
     Real(f64),
+    /// The value is an exact decimal number.
+    Decimal(Decimal),
     /// The value is a text string.
     Text(String),
     /// The value is a blob of data
     Blob(Vec<u8>),
+    /// The value is a calendar date.
+    Date(NaiveDate),
+    /// The value is a time of day.
+    Time(NaiveTime),
+    /// The value is a date and time without a time zone.
+    Timestamp(NaiveDateTime),
+    /// The value is a date and time at a UTC offset.
+    TimestampTz(DateTime<FixedOffset>),
+    /// The value is a UUID.
+    Uuid(Uuid),
+    /// The value is a JSON document.
+    Json(serde_json::Value),
+}
+
+/// How a database writes literal values, for composers that render values into the SQL text.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LiteralStyle {
+    /// Quotes doubled, blobs as `X'00ff'` and booleans as `1`/`0`, as used by SQLite, SQL Server
+    /// and Oracle
+    #[default]
+    Standard,
+    /// Quotes doubled, backslashes escaped, blobs as `X'00ff'` and timestamps converted to UTC
+    MySql,
+    /// Quotes doubled, text holding a backslash written as an `E'...'` escape string with the
+    /// backslashes doubled, blobs as `E'\\x00ff'::bytea` and other types cast, as in `'...'::date`,
+    /// so literals read the same whatever standard_conforming_strings is set to
+    Postgres,
+}
+
+impl Value {
+    /// Renders the value as a SQL literal. Text containing a NUL character, which can't be
+    /// written safely outside of MySQL, and NaN or infinite floats are errors.
+    pub fn to_sql_text(&self, style: LiteralStyle) -> Result<String, ()> {
+        let cast = |literal: String, pg_type: &str| match style {
+            LiteralStyle::Postgres => format!("{}::{}", literal, pg_type),
+            _ => literal,
+        };
+
+        Ok(match self {
+            Value::Null => "NULL".to_string(),
+            Value::Bool(b) => match (style, b) {
+                (LiteralStyle::Standard, true) => "1".to_string(),
+                (LiteralStyle::Standard, false) => "0".to_string(),
+                (_, true) => "TRUE".to_string(),
+                (_, false) => "FALSE".to_string(),
+            },
+            Value::Integer(i) => i.to_string(),
+            Value::Real(f) => {
+                if !f.is_finite() {
+                    //TODO: useful error
+                    return Err(());
+                }
+
+                // Debug always includes a decimal point or exponent, so the literal stays a float
+                format!("{:?}", f)
+            }
+            Value::Decimal(d) => d.to_string(),
+            Value::Text(t) => quote_text(t, style)?,
+            Value::Blob(b) => {
+                let hex: String = b.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+                match style {
+                    LiteralStyle::Postgres => {
+                        format!("{}::bytea", quote_text(&format!("\\x{}", hex), style)?)
+                    }
+                    _ => format!("X'{}'", hex),
+                }
+            }
+            Value::Date(d) => cast(format!("'{}'", d.format("%Y-%m-%d")), "date"),
+            Value::Time(t) => cast(format!("'{}'", t.format("%H:%M:%S%.f")), "time"),
+            Value::Timestamp(ts) => cast(
+                format!("'{}'", ts.format("%Y-%m-%d %H:%M:%S%.f")),
+                "timestamp",
+            ),
+            Value::TimestampTz(ts) => match style {
+                LiteralStyle::MySql => format!(
+                    "'{}'",
+                    ts.with_timezone(&Utc).format("%Y-%m-%d %H:%M:%S%.f")
+                ),
+                _ => cast(
+                    format!("'{}'", ts.format("%Y-%m-%d %H:%M:%S%.f%:z")),
+                    "timestamptz",
+                ),
+            },
+            Value::Uuid(u) => cast(format!("'{}'", u.to_hyphenated()), "uuid"),
+            Value::Json(j) => cast(quote_text(&j.to_string(), style)?, "jsonb"),
+        })
+    }
+}
+
+fn quote_text(t: &str, style: LiteralStyle) -> Result<String, ()> {
+    let mut quoted = String::with_capacity(t.len() + 2);
+
+    quoted.push('\'');
+
+    for c in t.chars() {
+        match (c, style) {
+            ('\'', _) => quoted.push_str("''"),
+            ('\\', LiteralStyle::MySql) | ('\\', LiteralStyle::Postgres) => {
+                quoted.push_str("\\\\")
+            }
+            ('\0', LiteralStyle::MySql) => quoted.push_str("\\0"),
+            //TODO: useful error
+            ('\0', _) => return Err(()),
+            (c, _) => quoted.push(c),
+        }
+    }
+
+    quoted.push('\'');
+
+    if style == LiteralStyle::Postgres && t.contains('\\') {
+        quoted.insert(0, 'E');
+    }
+
+    Ok(quoted)
 }
 
 pub trait ToValue {
     fn to_value(&self) -> Result<Value, ()>;
 
-    fn to_sql_text(&self) -> Result<String, ()> {
-        let value = self.to_value()?;
+    fn to_sql_text(&self, style: LiteralStyle) -> Result<String, ()> {
+        self.to_value()?.to_sql_text(style)
+    }
+}
 
-        Ok(match value {
-            Value::Integer(i) => i.to_string(),
-            Value::Real(f) => f.to_string(),
-            Value::Text(s) => format!("'{}'", s.to_string()),
-            Value::Blob(b) => format!("'{}'", String::from_utf8(b.to_vec()).unwrap()),
-            Value::Null => format!("NULL"),
-        })
+impl ToValue for Value {
+    fn to_value(&self) -> Result<Value, ()> {
+        Ok(self.clone())
     }
 }
 
@@ -41,7 +173,7 @@ impl ToValue for Null {
 
 impl ToValue for bool {
     fn to_value(&self) -> Result<Value, ()> {
-        Ok(Value::Integer(*self as i64))
+        Ok(Value::Bool(*self))
     }
 }
 
@@ -118,11 +250,45 @@ impl ToValue for Vec<u8> {
     }
 }
 
+impl ToValue for Decimal {
+    fn to_value(&self) -> Result<Value, ()> {
+        Ok(Value::Decimal(*self))
+    }
+}
+
+impl ToValue for NaiveDate {
+    fn to_value(&self) -> Result<Value, ()> {
+        Ok(Value::Date(*self))
+    }
+}
+
+impl ToValue for NaiveTime {
+    fn to_value(&self) -> Result<Value, ()> {
+        Ok(Value::Time(*self))
+    }
+}
+
+impl ToValue for NaiveDateTime {
+    fn to_value(&self) -> Result<Value, ()> {
+        Ok(Value::Timestamp(*self))
+    }
+}
+
 impl<Tz: TimeZone> ToValue for DateTime<Tz> {
     fn to_value(&self) -> Result<Value, ()> {
-        let utc = self.with_timezone(&Utc).format("%Y-%m-%dT%H:%M:%S%.f");
+        Ok(Value::TimestampTz(self.with_timezone(&self.offset().fix())))
+    }
+}
+
+impl ToValue for Uuid {
+    fn to_value(&self) -> Result<Value, ()> {
+        Ok(Value::Uuid(*self))
+    }
+}
 
-        Ok(Value::Text(utc.to_string()))
+impl ToValue for serde_json::Value {
+    fn to_value(&self) -> Result<Value, ()> {
+        Ok(Value::Json(self.clone()))
     }
 }
 
@@ -151,6 +317,18 @@ from_nullable!(i64);
 from_nullable!(f64);
 from_nullable!(String);
 from_nullable!(Vec<u8>);
+from_nullable!(Decimal);
+from_nullable!(NaiveDate);
+from_nullable!(NaiveTime);
+from_nullable!(NaiveDateTime);
+from_nullable!(Uuid);
+from_nullable!(serde_json::Value);
+
+/// A struct whose fields are bound by name, usually implemented with `#[derive(BindValues)]` from
+/// the `derive` feature.
+pub trait BindValues {
+    fn bind_values(&self) -> Result<BTreeMap<String, Vec<Value>>, ()>;
+}
 
 #[derive(Clone, Debug)]
 pub struct Rows {
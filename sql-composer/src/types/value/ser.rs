@@ -0,0 +1,780 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use serde::ser::{self, Impossible, Serialize};
+
+use super::Value;
+
+use crate::error::{Error, ErrorKind, Result};
+
+/// Binds each field of a struct or entry of a map by name, for a request type that already
+/// implements `Serialize`.
+///
+/// * a sequence binds each of its values to the name, as used by `IN (:bind(name))`, except a
+///   `Vec<u8>` or bytes which bind a single blob, as `#[derive(BindValues)]` binds them, though an
+///   empty `Vec<u8>` can't be told from any other empty sequence and binds no values
+/// * a nested struct or map binds its fields with dotted names, as in `:bind(page.limit)`
+/// * `None` and `()` bind NULL and a unit enum variant binds the name of the variant
+pub fn to_bind_values<T: Serialize + ?Sized>(
+    value: &T,
+) -> std::result::Result<BTreeMap<String, Vec<Value>>, ()> {
+    let mut values = BTreeMap::new();
+
+    //TODO: useful error, the InvalidBindValue the serializer fails with
+    value
+        .serialize(NamesSerializer::new(&mut values, None))
+        .map_err(|_| ())?;
+
+    Ok(values)
+}
+
+fn invalid(message: &str) -> Error {
+    ErrorKind::InvalidBindValue(message.to_string()).into()
+}
+
+macro_rules! reject(
+    ($message:expr; $($method:ident($($arg:ty), *)), +) => (
+        $(
+            fn $method(self, $(_: $arg), *) -> Result<Self::Ok> {
+                Err(invalid($message))
+            }
+        )+
+    )
+);
+
+macro_rules! bind_scalar(
+    ($($method:ident($arg:ty)), +) => (
+        $(
+            fn $method(self, v: $arg) -> Result<Self::Ok> {
+                self.bind(vec![ValueSerializer.$method(v)?])
+            }
+        )+
+    )
+);
+
+/// Serializes a struct or map, giving each of its fields a name under `prefix`.
+struct NamesSerializer<'a> {
+    values: &'a mut BTreeMap<String, Vec<Value>>,
+    prefix: Option<String>,
+    key:    Option<String>,
+}
+
+impl<'a> NamesSerializer<'a> {
+    fn new(values: &'a mut BTreeMap<String, Vec<Value>>, prefix: Option<String>) -> Self {
+        Self {
+            values,
+            prefix,
+            key: None,
+        }
+    }
+
+    fn field(&mut self, key: &str) -> FieldSerializer<'_> {
+        let name = match &self.prefix {
+            Some(prefix) => format!("{}.{}", prefix, key),
+            None => key.to_string(),
+        };
+
+        FieldSerializer {
+            values: self.values,
+            name,
+        }
+    }
+}
+
+impl<'a> ser::Serializer for NamesSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Impossible<(), Error>;
+    type SerializeTuple = Impossible<(), Error>;
+    type SerializeTupleStruct = Impossible<(), Error>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    reject!("only a struct or map can be bound by name";
+        serialize_bool(bool), serialize_i8(i8), serialize_i16(i16), serialize_i32(i32),
+        serialize_i64(i64), serialize_u8(u8), serialize_u16(u16), serialize_u32(u32),
+        serialize_u64(u64), serialize_f32(f32), serialize_f64(f64), serialize_char(char),
+        serialize_str(&str), serialize_bytes(&[u8]), serialize_unit_struct(&'static str),
+        serialize_unit_variant(&'static str, u32, &'static str)
+    );
+
+    fn serialize_none(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()> {
+        Err(invalid("only a struct or map can be bound by name"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(invalid("only a struct or map can be bound by name"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(invalid("only a struct or map can be bound by name"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(invalid("only a struct or map can be bound by name"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(invalid("only a struct or map can be bound by name"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(invalid("only a struct or map can be bound by name"))
+    }
+}
+
+impl<'a> ser::SerializeMap for NamesSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        let key = match key.serialize(ValueSerializer)? {
+            Value::Text(s) => s,
+            Value::Integer(i) => i.to_string(),
+            Value::Bool(b) => b.to_string(),
+            _ => return Err(invalid("a map key must be a string, integer or boolean")),
+        };
+
+        self.key = Some(key);
+
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| invalid("map value without a key"))?;
+
+        value.serialize(self.field(&key))
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeStruct for NamesSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()> {
+        value.serialize(self.field(key))
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Serializes the value of a single field, binding it to `name`.
+struct FieldSerializer<'a> {
+    values: &'a mut BTreeMap<String, Vec<Value>>,
+    name:   String,
+}
+
+impl<'a> FieldSerializer<'a> {
+    fn bind(self, values: Vec<Value>) -> Result<()> {
+        self.values.insert(self.name, values);
+
+        Ok(())
+    }
+}
+
+impl<'a> ser::Serializer for FieldSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = SeqSerializer<'a>;
+    type SerializeTuple = SeqSerializer<'a>;
+    type SerializeTupleStruct = SeqSerializer<'a>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = NamesSerializer<'a>;
+    type SerializeStruct = NamesSerializer<'a>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    bind_scalar!(
+        serialize_bool(bool), serialize_i8(i8), serialize_i16(i16), serialize_i32(i32),
+        serialize_i64(i64), serialize_u8(u8), serialize_u16(u16), serialize_u32(u32),
+        serialize_u64(u64), serialize_f32(f32), serialize_f64(f64), serialize_char(char),
+        serialize_str(&str), serialize_bytes(&[u8])
+    );
+
+    fn serialize_none(self) -> Result<()> {
+        self.bind(vec![Value::Null])
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        self.bind(vec![Value::Null])
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        self.bind(vec![Value::Null])
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        let value = ValueSerializer.serialize_unit_variant(name, variant_index, variant)?;
+
+        self.bind(vec![value])
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()> {
+        Err(invalid("only unit enum variants can be bound"))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(SeqSerializer {
+            field:  self,
+            values: Vec::with_capacity(len.unwrap_or(0)),
+            bytes:  Some(Vec::with_capacity(len.unwrap_or(0))),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        Ok(SeqSerializer {
+            field:  self,
+            values: Vec::with_capacity(len),
+            bytes:  None,
+        })
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_tuple(len)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(invalid("only unit enum variants can be bound"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(NamesSerializer::new(self.values, Some(self.name)))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Ok(NamesSerializer::new(self.values, Some(self.name)))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(invalid("only unit enum variants can be bound"))
+    }
+}
+
+/// Collects each value of a sequence, binding them all to the name of its field. A sequence of
+/// nothing but `u8`s, as a `Vec<u8>` is serialized, is bound as a single blob instead.
+struct SeqSerializer<'a> {
+    field:  FieldSerializer<'a>,
+    values: Vec<Value>,
+    bytes:  Option<Vec<u8>>,
+}
+
+impl<'a> SeqSerializer<'a> {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        if let Some(bytes) = &mut self.bytes {
+            match value.serialize(ByteSerializer) {
+                Ok(b) => bytes.push(b),
+                Err(_) => self.bytes = None,
+            }
+        }
+
+        self.values.push(value.serialize(ValueSerializer)?);
+
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeSeq for SeqSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<()> {
+        match self.bytes {
+            Some(bytes) if !bytes.is_empty() => self.field.bind(vec![Value::Blob(bytes)]),
+            _ => self.field.bind(self.values),
+        }
+    }
+}
+
+impl<'a> ser::SerializeTuple for SeqSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.field.bind(self.values)
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for SeqSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.field.bind(self.values)
+    }
+}
+
+/// Serializes a single `u8`, failing for anything else, to tell a `Vec<u8>` from other sequences.
+struct ByteSerializer;
+
+impl ser::Serializer for ByteSerializer {
+    type Ok = u8;
+    type Error = Error;
+
+    type SerializeSeq = Impossible<u8, Error>;
+    type SerializeTuple = Impossible<u8, Error>;
+    type SerializeTupleStruct = Impossible<u8, Error>;
+    type SerializeTupleVariant = Impossible<u8, Error>;
+    type SerializeMap = Impossible<u8, Error>;
+    type SerializeStruct = Impossible<u8, Error>;
+    type SerializeStructVariant = Impossible<u8, Error>;
+
+    reject!("not a byte";
+        serialize_bool(bool), serialize_i8(i8), serialize_i16(i16), serialize_i32(i32),
+        serialize_i64(i64), serialize_u16(u16), serialize_u32(u32), serialize_u64(u64),
+        serialize_f32(f32), serialize_f64(f64), serialize_char(char), serialize_str(&str),
+        serialize_bytes(&[u8]), serialize_none(), serialize_unit(),
+        serialize_unit_struct(&'static str),
+        serialize_unit_variant(&'static str, u32, &'static str)
+    );
+
+    fn serialize_u8(self, v: u8) -> Result<u8> {
+        Ok(v)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<u8> {
+        Err(invalid("not a byte"))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _value: &T,
+    ) -> Result<u8> {
+        Err(invalid("not a byte"))
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<u8> {
+        Err(invalid("not a byte"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(invalid("not a byte"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(invalid("not a byte"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(invalid("not a byte"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(invalid("not a byte"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(invalid("not a byte"))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(invalid("not a byte"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(invalid("not a byte"))
+    }
+}
+
+/// Serializes a single value, such as one value of a sequence.
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    type SerializeSeq = Impossible<Value, Error>;
+    type SerializeTuple = Impossible<Value, Error>;
+    type SerializeTupleStruct = Impossible<Value, Error>;
+    type SerializeTupleVariant = Impossible<Value, Error>;
+    type SerializeMap = Impossible<Value, Error>;
+    type SerializeStruct = Impossible<Value, Error>;
+    type SerializeStructVariant = Impossible<Value, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value> {
+        Ok(Value::Integer(i64::from(v)))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value> {
+        Ok(Value::Integer(i64::from(v)))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value> {
+        Ok(Value::Integer(i64::from(v)))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value> {
+        Ok(Value::Integer(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value> {
+        Ok(Value::Integer(i64::from(v)))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value> {
+        Ok(Value::Integer(i64::from(v)))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value> {
+        Ok(Value::Integer(i64::from(v)))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value> {
+        i64::try_from(v)
+            .map(Value::Integer)
+            .map_err(|_| invalid("integer out of range"))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value> {
+        Ok(Value::Real(f64::from(v)))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value> {
+        Ok(Value::Real(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value> {
+        Ok(Value::Text(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value> {
+        Ok(Value::Text(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value> {
+        Ok(Value::Blob(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value> {
+        Ok(Value::Text(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Value> {
+        Err(invalid("only unit enum variants can be bound"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(invalid("a sequence can't be nested in a bound value"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(invalid("a sequence can't be nested in a bound value"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(invalid("a sequence can't be nested in a bound value"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(invalid("only unit enum variants can be bound"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(invalid("a map can't be nested in a bound value"))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(invalid("a struct can't be nested in a bound value"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(invalid("only unit enum variants can be bound"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_bind_values;
+
+    use crate::composer::shared::SharedComposer;
+    use crate::parser::parse_template;
+    use crate::types::value::Value;
+    use crate::types::Span;
+
+    use serde_derive::Serialize;
+
+    use std::collections::{BTreeMap, HashMap};
+
+    #[derive(Serialize)]
+    enum Status {
+        Active,
+    }
+
+    #[derive(Serialize)]
+    struct Page {
+        limit:  u32,
+        offset: u32,
+    }
+
+    #[derive(Serialize)]
+    struct Search {
+        ids:      Vec<i64>,
+        name:     Option<String>,
+        status:   Status,
+        page:     Page,
+        #[serde(rename = "min_score")]
+        score:    f64,
+        data:     Vec<u8>,
+        codes:    Vec<u8>,
+    }
+
+    #[test]
+    fn test_bind_struct() {
+        let search = Search {
+            ids:    vec![1, 2],
+            name:   None,
+            status: Status::Active,
+            page:   Page {
+                limit:  10,
+                offset: 20,
+            },
+            score:  0.5,
+            data:   vec![0, 159, 146, 150],
+            codes:  vec![],
+        };
+
+        let values = to_bind_values(&search).expect("to_bind_values should work");
+
+        let mut expected = BTreeMap::new();
+
+        expected.insert("ids".to_string(), vec![Value::Integer(1), Value::Integer(2)]);
+        expected.insert("name".to_string(), vec![Value::Null]);
+        expected.insert("status".to_string(), vec![Value::Text("Active".into())]);
+        expected.insert("page.limit".to_string(), vec![Value::Integer(10)]);
+        expected.insert("page.offset".to_string(), vec![Value::Integer(20)]);
+        expected.insert("min_score".to_string(), vec![Value::Real(0.5)]);
+        expected.insert("data".to_string(), vec![Value::Blob(vec![0, 159, 146, 150])]);
+        expected.insert("codes".to_string(), vec![]);
+
+        assert_eq!(values, expected, "sequences, nulls, variants, bytes and dotted names");
+
+        let (_remaining, stmt) = parse_template(Span::new("SELECT id FROM t AS main WHERE id IN (:bind(ids EXPECTING MIN 1)) AND score > :bind(min_score) :paginate(page.limit, page.offset);"), None).unwrap();
+
+        let (bound_sql, bindings) = SharedComposer::postgres()
+            .compose(&stmt.item, &values, &HashMap::new())
+            .expect("compose should work");
+
+        assert_eq!(
+            bound_sql,
+            "SELECT id FROM t AS main WHERE id IN ( $1, $2 ) AND score > $3 LIMIT $4 OFFSET $5;"
+        );
+        assert_eq!(bindings.len(), 5, "bound values");
+    }
+
+    #[test]
+    fn test_bind_map() {
+        let mut filters = BTreeMap::new();
+
+        filters.insert("name", vec!["Steven", "Kenny"]);
+
+        let values = to_bind_values(&filters).expect("to_bind_values should work");
+
+        assert_eq!(
+            values["name"],
+            vec![Value::Text("Steven".into()), Value::Text("Kenny".into())],
+            "map entries are bound by key"
+        );
+
+        assert!(to_bind_values(&1i64).is_err(), "a scalar has no names");
+        assert!(
+            to_bind_values(&vec![vec![1i64]]).is_err(),
+            "a sequence has no names"
+        );
+
+        let mut nested = BTreeMap::new();
+
+        nested.insert("rows", vec![vec![1i64]]);
+
+        assert!(to_bind_values(&nested).is_err(), "nested sequences");
+    }
+}